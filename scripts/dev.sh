@@ -57,7 +57,13 @@ main() {
     
     # Check model status
     MODEL_STATUS=$(dfx canister call ai_canister health_check 2>/dev/null || echo "not_ready")
-    if [[ "$MODEL_STATUS" != *"healthy"* ]]; then
+    # A freshly loaded model reports loaded_unverified until a controller
+    # verifies its hash
+    if [[ "$MODEL_STATUS" == *"\"loaded_unverified\""* ]]; then
+        print_warning "Model loaded but not verified yet."
+    elif [[ "$MODEL_STATUS" == *"\"degraded\""* ]]; then
+        print_warning "Model loaded, but running simplified inference rather than a real ONNX forward pass."
+    elif [[ "$MODEL_STATUS" != *"\"healthy\""* ]]; then
         print_warning "Model not initialized. Run 'make setup' first."
     fi
     
@@ -712,7 +712,7 @@ print(f'(${i}:nat32, vec {{{bytes_str}}}, \"$CHUNK_HASH\")')
 " > "$TEMP_ARG_FILE" 2>/dev/null
                 
                 # Upload chunk using argument file
-                if dfx canister call ai_canister upload_model_chunk --argument-file "$TEMP_ARG_FILE" >/dev/null 2>&1; then
+                if dfx canister call ai_canister upload_model_chunk --argument-file "$TEMP_ARG_FILE" 2>/dev/null | grep -q "success = true"; then
                     UPLOADED_COUNT=$((UPLOADED_COUNT + 1))
                     BATCH_UPLOADED=$((BATCH_UPLOADED + 1))
                     print_info "✅ Uploaded chunk $i ($(($i + 1))/$TOTAL_CHUNKS)"
@@ -27,8 +27,14 @@ test_health() {
     
     # Test AI canister health
     AI_HEALTH=$(dfx canister call ai_canister health_check 2>/dev/null || echo "ERROR")
-    if [[ "$AI_HEALTH" == *"healthy"* ]]; then
+    # A freshly loaded model reports loaded_unverified until a controller
+    # verifies its hash, which this script doesn't do
+    if [[ "$AI_HEALTH" == *"\"healthy\""* ]]; then
         print_success "AI canister is healthy"
+    elif [[ "$AI_HEALTH" == *"\"loaded_unverified\""* ]]; then
+        print_warning "AI canister is up, but the loaded model is not verified yet"
+    elif [[ "$AI_HEALTH" == *"\"degraded\""* ]]; then
+        print_warning "AI canister is up, but verdicts come from simplified inference, not a real ONNX forward pass"
     else
         print_error "AI canister health check failed: $AI_HEALTH"
         return 1
@@ -0,0 +1,311 @@
+//! Container-level metadata parsing (EXIF, XMP, PNG text chunks) for
+//! forensic reporting. Only the handful of fields that matter for
+//! provenance are decoded; every read is bounds-checked because the input is
+//! untrusted.
+
+use crate::types::ForensicReport;
+
+// Substrings (lowercase) of software names that indicate editing or synthesis
+const EDITING_SOFTWARE_MARKERS: [&str; 18] = [
+    "photoshop", "gimp", "lightroom", "affinity", "pixelmator", "snapseed",
+    "facetune", "canva", "paint.net", "firefly", "stable diffusion", "midjourney",
+    "dall-e", "dalle", "comfyui", "automatic1111", "novelai", "invokeai",
+];
+
+// PNG text keys written by image generation front-ends
+const GENERATION_PARAMETER_KEYS: [&str; 4] = ["parameters", "prompt", "workflow", "invokeai_metadata"];
+
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+pub fn extract_forensics(data: &[u8]) -> ForensicReport {
+    let mut report = ForensicReport::default();
+    
+    if data.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
+        report.format = "PNG".to_string();
+        parse_png(data, &mut report);
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        report.format = "JPEG".to_string();
+        parse_jpeg(data, &mut report);
+    } else {
+        report.format = "unknown".to_string();
+    }
+    
+    let tools: Vec<String> = report.software.iter().chain(report.creator_tool.iter()).cloned().collect();
+    for tool in &tools {
+        add_editing_hints(tool, &mut report);
+    }
+    
+    report.metadata_stripped = report.present_fields.is_empty();
+    report
+}
+
+fn parse_jpeg(data: &[u8], report: &mut ForensicReport) {
+    let mut pos = 2; // Skip SOI
+    
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return;
+        }
+        let marker = data[pos + 1];
+        
+        // Fill bytes and standalone markers carry no length
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        // Start of scan / end of image: no more metadata segments
+        if marker == 0xDA || marker == 0xD9 {
+            return;
+        }
+        
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if length < 2 || pos + 2 + length > data.len() {
+            return;
+        }
+        let segment = &data[pos + 4..pos + 2 + length];
+        
+        match marker {
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                add_field(report, "exif");
+                report.has_exif = true;
+                parse_tiff(&segment[6..], report);
+            }
+            0xE1 if segment.starts_with(XMP_JPEG_HEADER) => {
+                parse_xmp(&segment[XMP_JPEG_HEADER.len()..], report);
+            }
+            0xED if segment.starts_with(b"Photoshop 3.0\0") => {
+                add_field(report, "photoshop_irb");
+                add_hint(report, "Photoshop image resource block present");
+            }
+            0xFE => {
+                add_field(report, "comment");
+                add_editing_hints(&String::from_utf8_lossy(segment), report);
+            }
+            _ => {}
+        }
+        
+        pos += 2 + length;
+    }
+}
+
+fn parse_png(data: &[u8], report: &mut ForensicReport) {
+    let mut pos = 8; // Skip signature
+    
+    while pos + 12 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let chunk_type = &data[pos + 4..pos + 8];
+        let Some(end) = (pos + 8).checked_add(length).filter(|end| end + 4 <= data.len()) else {
+            return;
+        };
+        let chunk = &data[pos + 8..end];
+        
+        match chunk_type {
+            b"eXIf" => {
+                add_field(report, "exif");
+                report.has_exif = true;
+                parse_tiff(chunk, report);
+            }
+            b"tEXt" | b"iTXt" | b"zTXt" => parse_png_text(chunk_type, chunk, report),
+            b"IEND" => return,
+            _ => {}
+        }
+        
+        pos = end + 4; // Skip CRC
+    }
+}
+
+fn parse_png_text(chunk_type: &[u8], chunk: &[u8], report: &mut ForensicReport) {
+    let Some(key_end) = chunk.iter().position(|&b| b == 0) else {
+        return;
+    };
+    let key = String::from_utf8_lossy(&chunk[..key_end]).to_string();
+    let rest = &chunk[key_end + 1..];
+    
+    // Only uncompressed text is decoded; compressed values are reported by key
+    let text = match chunk_type {
+        b"tEXt" => Some(String::from_utf8_lossy(rest).to_string()),
+        b"iTXt" if rest.len() >= 2 && rest[0] == 0 => {
+            // compression flag, method, language\0, translated keyword\0, text
+            let mut fields = rest[2..].splitn(3, |&b| b == 0);
+            let _language = fields.next();
+            let _translated = fields.next();
+            fields.next().map(|t| String::from_utf8_lossy(t).to_string())
+        }
+        _ => None,
+    };
+    
+    let key_lower = key.to_lowercase();
+    add_field(report, &format!("png_text:{}", key));
+    
+    if GENERATION_PARAMETER_KEYS.contains(&key_lower.as_str()) {
+        add_hint(report, &format!("Generation parameters embedded in PNG text chunk \"{}\"", key));
+    }
+    
+    match (key_lower.as_str(), text) {
+        ("software", Some(text)) => report.software = Some(truncate(&text)),
+        ("xml:com.adobe.xmp", Some(text)) => parse_xmp(text.as_bytes(), report),
+        ("creation time", Some(text)) if report.capture_time.is_none() => {
+            report.capture_time = Some(truncate(&text));
+        }
+        (_, Some(text)) => add_editing_hints(&text, report),
+        _ => {}
+    }
+}
+
+fn parse_xmp(packet: &[u8], report: &mut ForensicReport) {
+    add_field(report, "xmp");
+    report.has_xmp = true;
+    
+    let xmp = String::from_utf8_lossy(packet);
+    if let Some(tool) = xmp_value(&xmp, "xmp:CreatorTool") {
+        report.creator_tool = Some(truncate(&tool));
+    }
+    if xmp.contains("photoshop:History") || xmp.contains("xmpMM:History") {
+        add_field(report, "xmp_edit_history");
+        add_hint(report, "XMP edit history present");
+    }
+}
+
+/// Reads an XMP property written either as an attribute (`name="value"`) or
+/// as an element (`<name>value</name>`).
+fn xmp_value(xmp: &str, name: &str) -> Option<String> {
+    let attribute = format!("{}=\"", name);
+    if let Some(start) = xmp.find(&attribute).map(|i| i + attribute.len()) {
+        let end = xmp[start..].find('"')?;
+        return Some(xmp[start..start + end].to_string());
+    }
+    
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = xmp.find(&open)? + open.len();
+    let end = xmp[start..].find(&close)?;
+    Some(xmp[start..start + end].trim().to_string())
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+    
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+    
+    /// Reads an ASCII (type 2) entry, stored inline when it fits in 4 bytes.
+    fn ascii_at(&self, entry: usize) -> Option<String> {
+        if self.u16_at(entry + 2)? != 2 {
+            return None;
+        }
+        let count = self.u32_at(entry + 4)? as usize;
+        let start = if count <= 4 { entry + 8 } else { self.u32_at(entry + 8)? as usize };
+        let raw = self.data.get(start..start.checked_add(count)?)?;
+        let text = String::from_utf8_lossy(raw).trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then(|| truncate(&text))
+    }
+    
+    /// Visits each 12-byte entry of the IFD at `offset` as `(tag, entry_offset)`.
+    fn entries(&self, offset: usize) -> Vec<(u16, usize)> {
+        let Some(count) = self.u16_at(offset) else {
+            return Vec::new();
+        };
+        (0..count as usize)
+            .map(|i| offset + 2 + i * 12)
+            .take_while(|&entry| entry + 12 <= self.data.len())
+            .filter_map(|entry| Some((self.u16_at(entry)?, entry)))
+            .collect()
+    }
+}
+
+fn parse_tiff(data: &[u8], report: &mut ForensicReport) {
+    let little_endian = match data.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return,
+    };
+    let tiff = Tiff { data, little_endian };
+    if tiff.u16_at(2) != Some(42) {
+        return;
+    }
+    let Some(ifd0) = tiff.u32_at(4) else {
+        return;
+    };
+    
+    let mut exif_ifd = None;
+    for (tag, entry) in tiff.entries(ifd0 as usize) {
+        match tag {
+            TAG_MAKE => set_exif_field(report, "make", tiff.ascii_at(entry), |r, v| r.camera_make = v),
+            TAG_MODEL => set_exif_field(report, "model", tiff.ascii_at(entry), |r, v| r.camera_model = v),
+            TAG_SOFTWARE => set_exif_field(report, "software", tiff.ascii_at(entry), |r, v| r.software = v),
+            TAG_DATE_TIME if report.capture_time.is_none() => {
+                set_exif_field(report, "date_time", tiff.ascii_at(entry), |r, v| r.capture_time = v);
+            }
+            TAG_EXIF_IFD => exif_ifd = tiff.u32_at(entry + 8),
+            _ => {}
+        }
+    }
+    
+    // DateTimeOriginal is the capture time; DateTime is often the last edit
+    if let Some(offset) = exif_ifd.filter(|&o| o != ifd0) {
+        for (tag, entry) in tiff.entries(offset as usize) {
+            if tag == TAG_DATE_TIME_ORIGINAL {
+                set_exif_field(report, "date_time_original", tiff.ascii_at(entry), |r, v| r.capture_time = v);
+            }
+        }
+    }
+}
+
+fn set_exif_field(
+    report: &mut ForensicReport,
+    name: &str,
+    value: Option<String>,
+    assign: impl FnOnce(&mut ForensicReport, Option<String>),
+) {
+    if value.is_some() {
+        add_field(report, &format!("exif:{}", name));
+        assign(report, value);
+    }
+}
+
+fn add_editing_hints(text: &str, report: &mut ForensicReport) {
+    let lower = text.to_lowercase();
+    for marker in EDITING_SOFTWARE_MARKERS {
+        if lower.contains(marker) {
+            add_hint(report, &format!("Editing/generation software referenced: {}", marker));
+        }
+    }
+}
+
+fn add_field(report: &mut ForensicReport, field: &str) {
+    if !report.present_fields.iter().any(|f| f == field) {
+        report.present_fields.push(field.to_string());
+    }
+}
+
+fn add_hint(report: &mut ForensicReport, hint: &str) {
+    if !report.editing_software_hints.iter().any(|h| h == hint) {
+        report.editing_software_hints.push(hint.to_string());
+    }
+}
+
+// Metadata values are attacker-controlled; keep echoed strings short
+fn truncate(text: &str) -> String {
+    text.chars().take(256).collect()
+}
@@ -0,0 +1,5 @@
+pub mod metadata;
+pub mod prescreen;
+
+pub use metadata::*;
+pub use prescreen::*;
@@ -0,0 +1,27 @@
+use crate::types::{ForensicReport, PrescreenResult, RiskLevel};
+
+/// Metadata-only triage: software traces of editing or generation tools are
+/// high risk, fully stripped metadata is elevated, anything else is low.
+/// Metadata is trivially forged, so this only decides whether full analysis
+/// is worth its cycles; it never clears an image.
+pub fn prescreen(report: &ForensicReport) -> PrescreenResult {
+    let mut reasons = Vec::new();
+    
+    let risk_level = if !report.editing_software_hints.is_empty() {
+        reasons.extend(report.editing_software_hints.iter().cloned());
+        RiskLevel::High
+    } else if report.metadata_stripped {
+        reasons.push("All metadata is stripped".to_string());
+        RiskLevel::Elevated
+    } else {
+        if report.camera_make.is_some() || report.camera_model.is_some() {
+            reasons.push("Camera make/model present".to_string());
+        }
+        if report.capture_time.is_some() {
+            reasons.push("Capture time present".to_string());
+        }
+        RiskLevel::Low
+    };
+    
+    PrescreenResult { risk_level, reasons }
+}
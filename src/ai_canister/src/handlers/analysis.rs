@@ -1,21 +1,211 @@
 use crate::types::*;
-use crate::model::VeriChainModel;
-use crate::utils::validate_image_data;
-use ic_cdk::api::time;
+use crate::types::prediction::PredictionLabel;
+use crate::model::{VeriChainModel, MODEL_VERSION};
+use crate::storage::{ModelStorage, QuarantineList};
+use crate::utils::{
+    validate_image_data, validate_image_size, detect_image_format, generate_analysis_id,
+    calculate_hash, SUPPORTED_IMAGE_FORMATS,
+};
+use crate::preprocessing::{
+    decode_image, preprocess_image, preprocess_image_with_filter, preprocess_image_from_bytes,
+    difference_hash, downsample_tensor, get_model_input_shape, letterbox, aspect_ratio,
+    luminance_entropy,
+};
+use image::DynamicImage;
+use crate::forensics::{extract_forensics, prescreen};
+use image::imageops::FilterType;
+use ic_cdk::api::{instruction_counter, time};
 
-pub fn handle_analyze(image_data: Vec<u8>, model: &VeriChainModel) -> VeriChainResult<MediaAnalysisResult> {
-    let start_time = time();
+/// Resize filters compared by `analyze_robust`.
+const ROBUSTNESS_FILTERS: [(&str, FilterType); 3] = [
+    ("lanczos3", FilterType::Lanczos3),
+    ("catmull_rom", FilterType::CatmullRom),
+    ("triangle", FilterType::Triangle),
+];
+
+/// Spatial stride for the public preview of a preprocessed tensor (3x28x28).
+const TENSOR_PREVIEW_STRIDE: u32 = 8;
+
+/// Most thresholds `analyze_threshold_sweep` evaluates in one call.
+const MAX_SWEEP_THRESHOLDS: usize = 20;
+
+/// Largest dHash Hamming distance (out of 64 bits) still treated as the same
+/// picture after recompression or resizing.
+const SIMILAR_IMAGE_MAX_HASH_DISTANCE: u32 = 10;
+
+fn log_if_slow(endpoint: &str, analysis_id: &str, image_data: &[u8], config: &CanisterConfig) {
+    let threshold = config.slow_request_instruction_threshold();
+    let instructions = instruction_counter();
     
-    // Validate input
-    validate_image_data(&image_data)?;
+    if threshold > 0 && instructions > threshold {
+        ic_cdk::println!(
+            "⚠️ Slow {} [{}]: {} instructions (threshold {}), input {} bytes, media type {}",
+            endpoint,
+            analysis_id,
+            instructions,
+            threshold,
+            image_data.len(),
+            detect_image_format(image_data).unwrap_or("unknown")
+        );
+    }
+}
+
+/// A decoded input ready for preprocessing, with the reliability caveats
+/// found while decoding it.
+struct DecodedInput {
+    image: DynamicImage,
+    warnings: Vec<String>,
+    upscaled: bool,
+    /// Color type before conversion to RGB, e.g. "Rgba8" or "L8"
+    source_color_type: String,
+    /// Luminance entropy in bits of the image the model will see
+    entropy: f64,
+}
+
+/// Decodes the input and applies the configured aspect-ratio policy, either
+/// rejecting banner/panorama shapes or letterboxing them with a warning.
+fn decode_for_analysis(image_data: &[u8], config: &CanisterConfig) -> VeriChainResult<DecodedInput> {
+    let image = decode_image(image_data)?;
+    let source_color_type = format!("{:?}", image.color());
+    let mut warnings = Vec::new();
+    
+    let upscaled = is_below_native_resolution(&image, config);
+    if upscaled {
+        warnings.push(format!(
+            "Low resolution {}x{} (minimum {}px per side); image was upscaled, verdict may be less reliable",
+            image.width(), image.height(), config.min_native_resolution()
+        ));
+    }
+    
+    if exceeds_max_aspect_ratio(&image, config) {
+        if !config.letterbox_extreme_aspect_ratio() {
+            return Err(describe_extreme_aspect_ratio(&image, config));
+        }
+        warnings.push(format!(
+            "Extreme aspect ratio {}x{} ({:.1}:1); image was letterboxed, verdict may be less reliable",
+            image.width(), image.height(), aspect_ratio(&image)
+        ));
+        let image = letterbox(&image);
+        let entropy = check_entropy(&image, config)?;
+        return Ok(DecodedInput { image, warnings, upscaled, source_color_type, entropy });
+    }
+    
+    let entropy = check_entropy(&image, config)?;
+    Ok(DecodedInput { image, warnings, upscaled, source_color_type, entropy })
+}
+
+/// Rejects near-uniform images, which carry too little information for a
+/// trustworthy verdict; returns the entropy otherwise.
+fn check_entropy(image: &DynamicImage, config: &CanisterConfig) -> VeriChainResult<f64> {
+    let entropy = luminance_entropy(image);
+    if entropy < config.min_image_entropy() {
+        return Err(format!(
+            "Invalid input: insufficient image entropy ({:.2} bits, minimum {:.2})",
+            entropy, config.min_image_entropy()
+        ));
+    }
+    Ok(entropy)
+}
+
+fn is_below_native_resolution(image: &DynamicImage, config: &CanisterConfig) -> bool {
+    image.width().min(image.height()) < config.min_native_resolution()
+}
+
+fn exceeds_max_aspect_ratio(image: &DynamicImage, config: &CanisterConfig) -> bool {
+    config.max_aspect_ratio() > 0.0 && aspect_ratio(image) > config.max_aspect_ratio()
+}
+
+fn describe_extreme_aspect_ratio(image: &DynamicImage, config: &CanisterConfig) -> String {
+    format!(
+        "Invalid input: extreme aspect ratio {}x{} ({:.1}:1, maximum {:.1}:1)",
+        image.width(), image.height(), aspect_ratio(image), config.max_aspect_ratio()
+    )
+}
+
+/// Applies the configured uncertainty margin and manipulation threshold to a
+/// fresh prediction; every analysis path goes through here.
+pub(crate) fn apply_decision_policy(prediction: &mut PredictionResult, config: &CanisterConfig) {
+    prediction.apply_low_confidence_margin(config.low_confidence_margin);
+    prediction.apply_manipulation_threshold(config.manipulation_threshold());
+}
+
+/// Checks shared by every analysis entry point before any inference runs.
+fn ensure_ready_for_analysis(
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> VeriChainResult<()> {
+    // Never analyze against a partially assembled model
+    if storage.is_initialization_in_progress() {
+        let (processed, total) = storage.get_initialization_progress();
+        let percent = if total > 0 { processed as f64 / total as f64 * 100.0 } else { 0.0 };
+        return Err(format!(
+            "Model is initializing ({}/{} chunks, {:.1}% complete). Please retry later.",
+            processed, total, percent
+        ));
+    }
     
     // Check if model is loaded
     if !model.is_loaded() {
         return Err("Model is not loaded. Please initialize the model first.".to_string());
     }
     
+    if config.require_real_model() && model.inference_mode() != "onnx_real" {
+        return Err(format!(
+            "Feature unavailable: real model inference not available (inference mode: {})",
+            model.inference_mode()
+        ));
+    }
+    
+    if config.require_verified_model() && !model.is_verified() {
+        return Err("Model is loaded but not verified. A controller must call verify_loaded_model first.".to_string());
+    }
+    
+    Ok(())
+}
+
+pub fn handle_analyze(
+    image_data: Vec<u8>,
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+    quarantine: &QuarantineList,
+) -> VeriChainResult<MediaAnalysisResult> {
+    let start_time = time();
+    let analysis_id = generate_analysis_id(&image_data, start_time);
+    
+    // Validate input
+    validate_image_data(&image_data)?;
+    
+    // Known-bad content gets the quarantine verdict even without a model
+    if let Some(entry) = quarantine.get(&calculate_hash(&image_data)) {
+        let prediction = PredictionResult::quarantined();
+        return Ok(MediaAnalysisResult {
+            analysis_id,
+            summary: prediction.summary(&config.class_display_names()),
+            prediction,
+            processing_time_ms: (time() - start_time) / 1_000_000,
+            input_size: image_data.len() as u32,
+            model_version: MODEL_VERSION.to_string(),
+            processed_at: time(),
+            quarantine_reason: Some(entry.reason.clone()),
+            warnings: Vec::new(),
+            upscaled: false,
+            source_color_type: None,
+            entropy: None,
+            verdict_bias: config.verdict_bias(),
+        });
+    }
+    
+    ensure_ready_for_analysis(model, storage, config)?;
+    
+    let input = decode_for_analysis(&image_data, config)?;
+    
     // Perform prediction with realistic processing time
-    let prediction = model.predict(&image_data)?;
+    let mut prediction = model.predict(input.image, &storage.get_normalization_stats())?;
+    apply_decision_policy(&mut prediction, config);
+    
+    log_if_slow("analyze", &analysis_id, &image_data, config);
     
     // Calculate processing time and ensure minimum realistic duration
     let mut processing_time = (time() - start_time) / 1_000_000; // Convert to milliseconds
@@ -26,18 +216,365 @@ pub fn handle_analyze(image_data: Vec<u8>, model: &VeriChainModel) -> VeriChainR
     }
     
     Ok(MediaAnalysisResult {
+        analysis_id,
+        summary: prediction.summary(&config.class_display_names()),
         prediction,
         processing_time_ms: processing_time,
         input_size: image_data.len() as u32,
-        model_version: "VeriChain-ViT-v1.0".to_string(),
+        model_version: MODEL_VERSION.to_string(),
+        processed_at: time(),
+        quarantine_reason: None,
+        warnings: input.warnings,
+        upscaled: input.upscaled,
+        source_color_type: Some(input.source_color_type),
+        entropy: Some(input.entropy),
+        verdict_bias: config.verdict_bias(),
+    })
+}
+
+/// Runs inference once per resize filter; disagreement or high confidence
+/// variance between them marks a verdict that depends on preprocessing
+/// details and should go to human review.
+pub fn handle_analyze_robust(
+    image_data: Vec<u8>,
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> VeriChainResult<RobustResult> {
+    let analysis_id = generate_analysis_id(&image_data, time());
+    
+    validate_image_data(&image_data)?;
+    ensure_ready_for_analysis(model, storage, config)?;
+    
+    let image = decode_for_analysis(&image_data, config)?.image;
+    let stats = storage.get_normalization_stats();
+    
+    let mut predictions = Vec::with_capacity(ROBUSTNESS_FILTERS.len());
+    for (name, filter) in ROBUSTNESS_FILTERS {
+        let tensor = preprocess_image_with_filter(image.clone(), filter, &stats)?;
+        let mut prediction = model.predict_preprocessed(&tensor)?;
+        apply_decision_policy(&mut prediction, config);
+        
+        predictions.push(FilterPrediction {
+            filter: name.to_string(),
+            prediction,
+        });
+    }
+    
+    let first_label = &predictions[0].prediction.label;
+    let label_agreement = predictions.iter().all(|p| &p.prediction.label == first_label);
+    
+    let count = predictions.len() as f64;
+    let mean_confidence = predictions.iter().map(|p| p.prediction.raw_confidence).sum::<f64>() / count;
+    let confidence_variance = predictions.iter()
+        .map(|p| (p.prediction.raw_confidence - mean_confidence).powi(2))
+        .sum::<f64>() / count;
+    
+    log_if_slow("analyze_robust", &analysis_id, &image_data, config);
+    
+    Ok(RobustResult {
+        analysis_id,
+        predictions,
+        label_agreement,
+        mean_confidence,
+        confidence_variance,
         processed_at: time(),
     })
 }
 
+/// Runs inference once and re-decides `is_manipulated` at each requested
+/// threshold, for dashboards showing how sensitive a verdict is.
+pub fn handle_analyze_threshold_sweep(
+    image_data: Vec<u8>,
+    thresholds: Vec<f64>,
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> VeriChainResult<SweepResult> {
+    let analysis_id = generate_analysis_id(&image_data, time());
+    
+    if thresholds.is_empty() || thresholds.len() > MAX_SWEEP_THRESHOLDS {
+        return Err(format!(
+            "Invalid input: expected 1 to {} thresholds, got {}",
+            MAX_SWEEP_THRESHOLDS, thresholds.len()
+        ));
+    }
+    if let Some(bad) = thresholds.iter().find(|t| !(0.0..=1.0).contains(*t)) {
+        return Err(format!("Invalid input: thresholds must be within [0.0, 1.0], got {}", bad));
+    }
+    
+    validate_image_data(&image_data)?;
+    ensure_ready_for_analysis(model, storage, config)?;
+    
+    let image = decode_for_analysis(&image_data, config)?.image;
+    let mut prediction = model.predict(image, &storage.get_normalization_stats())?;
+    apply_decision_policy(&mut prediction, config);
+    
+    let verdicts = thresholds.into_iter()
+        .map(|threshold| {
+            let mut at_threshold = prediction.clone();
+            at_threshold.apply_manipulation_threshold(threshold);
+            ThresholdVerdict { threshold, is_manipulated: at_threshold.is_manipulated }
+        })
+        .collect();
+    
+    log_if_slow("analyze_threshold_sweep", &analysis_id, &image_data, config);
+    
+    Ok(SweepResult {
+        analysis_id,
+        prediction,
+        verdicts,
+        processed_at: time(),
+    })
+}
+
+/// Compares a candidate against a known-authentic reference. A candidate that
+/// looks like the reference but gets a different verdict is likely an edited
+/// copy of it.
+pub fn handle_verify_against_reference(
+    candidate: Vec<u8>,
+    reference: Vec<u8>,
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> VeriChainResult<ReferenceMatchResult> {
+    validate_image_data(&candidate).map_err(|e| format!("Candidate: {}", e))?;
+    validate_image_data(&reference).map_err(|e| format!("Reference: {}", e))?;
+    ensure_ready_for_analysis(model, storage, config)?;
+    
+    let candidate_image = decode_for_analysis(&candidate, config)?.image;
+    let reference_image = decode_for_analysis(&reference, config)?.image;
+    
+    let hash_distance = (difference_hash(&candidate_image) ^ difference_hash(&reference_image)).count_ones();
+    let perceptually_similar = hash_distance <= SIMILAR_IMAGE_MAX_HASH_DISTANCE;
+    
+    let stats = storage.get_normalization_stats();
+    let mut candidate_prediction = model.predict_preprocessed(&preprocess_image(candidate_image, &stats)?)?;
+    apply_decision_policy(&mut candidate_prediction, config);
+    let mut reference_prediction = model.predict_preprocessed(&preprocess_image(reference_image, &stats)?)?;
+    apply_decision_policy(&mut reference_prediction, config);
+    
+    let likely_manipulated_copy = perceptually_similar
+        && candidate_prediction.label != reference_prediction.label;
+    
+    Ok(ReferenceMatchResult {
+        hash_distance,
+        perceptually_similar,
+        candidate_prediction,
+        reference_prediction,
+        likely_manipulated_copy,
+        processed_at: time(),
+    })
+}
+
+/// Candid-encodes an analysis outcome (including errors) so inter-canister
+/// callers can forward it as an opaque blob and decode it with
+/// `candid::decode_one::<Result<MediaAnalysisResult, String>>`.
+pub fn handle_encode_analysis(result: &VeriChainResult<MediaAnalysisResult>) -> VeriChainResult<Vec<u8>> {
+    candid::encode_one(result)
+        .map_err(|e| format!("Failed to encode analysis result: {}", e))
+}
+
+/// Size of the `analyze_packed` result.
+pub const PACKED_RESULT_LEN: usize = 16;
+
+/// Packs an analysis outcome into a fixed 16-byte record for callers that
+/// want to skip Candid decoding:
+///
+/// | bytes  | content                                                    |
+/// |--------|------------------------------------------------------------|
+/// | 0      | flags: bit 0 success, bit 1 uncertain, bits 2-3 label,      |
+/// |        | (0 real, 1 AI-generated, 2 deepfake, 3 quarantined),        |
+/// |        | bit 4 manipulated                                          |
+/// | 1..5   | real score, f32 little-endian                              |
+/// | 5..9   | AI-generated score, f32 little-endian                      |
+/// | 9..13  | deepfake score, f32 little-endian                          |
+/// | 13..16 | reserved, zero                                             |
+///
+/// A failed analysis is all zeros; use `analyze` to get the error message.
+pub fn handle_pack_analysis(result: &VeriChainResult<MediaAnalysisResult>) -> [u8; PACKED_RESULT_LEN] {
+    let mut packed = [0u8; PACKED_RESULT_LEN];
+    
+    let prediction = match result {
+        Ok(analysis) => &analysis.prediction,
+        Err(_) => return packed,
+    };
+    
+    let label_bits: u8 = match prediction.label {
+        PredictionLabel::Real => 0,
+        PredictionLabel::AIGenerated => 1,
+        PredictionLabel::Deepfake => 2,
+        PredictionLabel::Quarantined => 3,
+    };
+    packed[0] = 0b1
+        | (u8::from(prediction.uncertain) << 1)
+        | (label_bits << 2)
+        | (u8::from(prediction.is_manipulated) << 4);
+    
+    let scores = &prediction.raw_scores;
+    packed[1..5].copy_from_slice(&(scores.real as f32).to_le_bytes());
+    packed[5..9].copy_from_slice(&(scores.ai_generated as f32).to_le_bytes());
+    packed[9..13].copy_from_slice(&(scores.deepfake as f32).to_le_bytes());
+    
+    packed
+}
+
+/// True only when analyze would get past its readiness checks: the model is
+/// fully initialized, loaded, and verified if the config requires it.
+pub fn handle_is_ready_for_analysis(
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> bool {
+    storage.is_initialized() && ensure_ready_for_analysis(model, storage, config).is_ok()
+}
+
+/// Reports container metadata (EXIF, XMP, PNG text) without running the
+/// model, as corroborating evidence for investigators.
+pub fn handle_extract_media_forensics(image_data: Vec<u8>) -> VeriChainResult<ForensicReport> {
+    validate_image_data(&image_data)?;
+    Ok(extract_forensics(&image_data))
+}
+
+/// Cheap metadata triage so clients can decide whether full analysis is
+/// worth running.
+pub fn handle_prescreen(image_data: Vec<u8>) -> VeriChainResult<PrescreenResult> {
+    validate_image_data(&image_data)?;
+    Ok(prescreen(&extract_forensics(&image_data)))
+}
+
+/// 64-bit difference hash as 16 hex characters. Near-duplicates differ in
+/// few bits, so clients compare Hamming distances rather than equality.
+pub fn handle_compute_phash(image_data: Vec<u8>) -> VeriChainResult<String> {
+    validate_image_data(&image_data)?;
+    let image = decode_image(&image_data)?;
+    Ok(format!("{:016x}", difference_hash(&image)))
+}
+
+/// Returns exactly what the model would receive for `image_data`, without
+/// running inference. Only controllers get the full tensor; everyone gets
+/// its hash and a strided preview to compare against their own pipeline.
+pub fn handle_get_preprocessed_tensor(
+    image_data: Vec<u8>,
+    storage: &ModelStorage,
+    include_full_tensor: bool,
+) -> VeriChainResult<PreprocessedTensor> {
+    validate_image_data(&image_data)?;
+    
+    let tensor = preprocess_image_from_bytes(&image_data, &storage.get_normalization_stats())?;
+    let tensor_bytes: Vec<u8> = tensor.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let (channels, height, width) = get_model_input_shape();
+    
+    Ok(PreprocessedTensor {
+        shape: vec![channels, height, width],
+        tensor_sha256: calculate_hash(&tensor_bytes),
+        downsample_stride: TENSOR_PREVIEW_STRIDE,
+        downsampled: downsample_tensor(&tensor, TENSOR_PREVIEW_STRIDE),
+        tensor: if include_full_tensor { Some(tensor) } else { None },
+    })
+}
+
+/// Runs every input check without short-circuiting so a rejected image's
+/// problems can all be seen in one call.
+pub fn handle_validate_image_detailed(image_data: Vec<u8>, config: &CanisterConfig) -> ValidationReport {
+    let mut issues = Vec::new();
+    
+    let size_ok = match validate_image_size(&image_data) {
+        Ok(()) => true,
+        Err(e) => {
+            issues.push(e);
+            false
+        }
+    };
+    
+    let detected_format = detect_image_format(&image_data);
+    let format_ok = detected_format.is_some();
+    if !format_ok {
+        issues.push("Unsupported image format. Only PNG, JPEG, and JPG are supported".to_string());
+    }
+    
+    let (decodable, width, height, dimensions_ok) = match decode_image(&image_data) {
+        Ok(image) => {
+            let dimensions_ok = !exceeds_max_aspect_ratio(&image, config) || config.letterbox_extreme_aspect_ratio();
+            if !dimensions_ok {
+                issues.push(describe_extreme_aspect_ratio(&image, config));
+            }
+            (true, Some(image.width()), Some(image.height()), dimensions_ok)
+        }
+        Err(e) => {
+            issues.push(e);
+            (false, None, None, false)
+        }
+    };
+    
+    ValidationReport {
+        format_ok,
+        size_ok,
+        dimensions_ok,
+        decodable,
+        detected_format: detected_format.map(str::to_string),
+        width,
+        height,
+        issues,
+    }
+}
+
 pub fn handle_validate_image_format(image_data: Vec<u8>) -> bool {
     validate_image_data(&image_data).is_ok()
 }
 
 pub fn handle_get_supported_formats() -> Vec<String> {
-    vec!["PNG".to_string(), "JPEG".to_string(), "JPG".to_string()]
+    SUPPORTED_IMAGE_FORMATS.iter().map(|f| f.to_string()).collect()
+}
+
+/// Capabilities by media category. Only still images are analyzed today,
+/// so the video and social lists are empty rather than aspirational.
+pub fn handle_get_supported_media() -> SupportedMedia {
+    SupportedMedia {
+        image_formats: handle_get_supported_formats(),
+        video_formats: Vec::new(),
+        social_platforms: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analysis_is_rejected_while_model_is_initializing() {
+        let mut storage = ModelStorage::new();
+        storage.store_metadata(ModelMetadata {
+            original_file: "model.onnx".to_string(),
+            original_size: 8,
+            total_chunks: 2,
+            chunk_size_mb: 1.0,
+            version: MODEL_VERSION.to_string(),
+            normalization: None,
+        }).unwrap();
+        for id in 0..2 {
+            storage.store_chunk(ModelChunk { id, data: vec![0; 4], hash: String::new() }).unwrap();
+        }
+        storage.start_initialization().unwrap();
+        storage.process_chunks_batch(1).unwrap();
+
+        let model = VeriChainModel::new().unwrap();
+        let err = ensure_ready_for_analysis(&model, &storage, &CanisterConfig::default()).unwrap_err();
+        assert!(err.starts_with("Model is initializing (1/2 chunks, 50.0% complete)"), "{}", err);
+    }
+
+    #[test]
+    fn require_real_model_refuses_simplified_inference() {
+        let model = VeriChainModel::loaded_for_test(vec![0; 16]);
+        assert_ne!(model.inference_mode(), "onnx_real");
+        
+        let config = CanisterConfig {
+            require_real_model: Some(true),
+            ..CanisterConfig::default()
+        };
+        let err = ensure_ready_for_analysis(&model, &ModelStorage::new(), &config).unwrap_err();
+        assert!(err.starts_with("Feature unavailable"), "{}", err);
+        
+        assert!(ensure_ready_for_analysis(&model, &ModelStorage::new(), &CanisterConfig::default()).is_ok());
+    }
 }
@@ -0,0 +1,57 @@
+use crate::types::*;
+use crate::types::prediction::PredictionLabel;
+use crate::storage::{AnalyticsState, ConfidenceHistory};
+use ic_cdk::api::instruction_counter;
+
+/// Counts a completed analysis under the input's sniffed format and, when
+/// there is a single verdict, under its verdict bucket.
+pub fn handle_record_analysis(
+    analytics: &mut AnalyticsState,
+    format: Option<&str>,
+    prediction: Option<&PredictionResult>,
+    config: &CanisterConfig,
+) {
+    analytics.record_format(format.unwrap_or("unknown"));
+    
+    if let Some(prediction) = prediction {
+        analytics.record_verdict(prediction, config.analytics_min_confidence());
+    }
+}
+
+/// Tracks the confidence of model verdicts; quarantine hits never ran the
+/// model, so they would only skew the distribution.
+pub fn handle_record_confidence(history: &mut ConfidenceHistory, prediction: &PredictionResult) {
+    if prediction.label != PredictionLabel::Quarantined {
+        history.push(prediction.raw_confidence);
+    }
+}
+
+pub fn handle_get_confidence_histogram(history: &ConfidenceHistory, buckets: u32) -> VeriChainResult<Vec<u32>> {
+    const MAX_HISTOGRAM_BUCKETS: u32 = 100;
+    
+    if buckets == 0 || buckets > MAX_HISTOGRAM_BUCKETS {
+        return Err(format!(
+            "buckets must be between 1 and {}, got {}",
+            MAX_HISTOGRAM_BUCKETS, buckets
+        ));
+    }
+    
+    Ok(history.histogram(buckets as usize))
+}
+
+pub fn handle_record_instructions(analytics: &mut AnalyticsState) {
+    analytics.add_instructions(instruction_counter());
+}
+
+pub fn handle_get_analytics(analytics: &AnalyticsState) -> AnalyticsReport {
+    let (authentic_detected, deepfake_detected, uncertain) = analytics.verdict_counts();
+    
+    AnalyticsReport {
+        total_analyses: analytics.total_analyses(),
+        format_breakdown: analytics.format_breakdown(),
+        authentic_detected,
+        deepfake_detected,
+        uncertain,
+        total_instructions_consumed: analytics.total_instructions(),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::types::*;
+use crate::model::VeriChainModel;
+
+pub fn handle_update_config(
+    current: &mut CanisterConfig,
+    model: &mut VeriChainModel,
+    new_config: CanisterConfig,
+) -> VeriChainResult<String> {
+    new_config.validate()?;
+    *current = new_config;
+    
+    // A narrowed allow-list applies to the model that is already loaded too
+    if let Some(hash) = model.get_model_hash().filter(|_| model.is_loaded()) {
+        if !current.is_model_approved(&hash) {
+            model.unload();
+            return Ok(format!(
+                "Configuration updated successfully; unloaded model {} as it is no longer in approved_model_hashes",
+                hash
+            ));
+        }
+    }
+    
+    Ok("Configuration updated successfully".to_string())
+}
+
+pub fn handle_set_confidence_threshold(current: &mut CanisterConfig, threshold: f64) -> VeriChainResult<String> {
+    let mut updated = current.clone();
+    updated.confidence_threshold = Some(threshold);
+    updated.validate()?;
+    *current = updated;
+    
+    // verdict_bias can still move the threshold that is actually applied
+    Ok(format!(
+        "Confidence threshold set to {}; effective manipulation threshold is {}",
+        threshold,
+        current.manipulation_threshold()
+    ))
+}
+
+pub fn handle_get_effective_config(config: &CanisterConfig) -> EffectiveConfig {
+    let defaults = CanisterConfig::default().entries();
+    
+    let entries = config.entries()
+        .into_iter()
+        .zip(defaults)
+        .map(|((name, value), (_, default_value))| ConfigEntry {
+            name: name.to_string(),
+            overridden: value != default_value,
+            value,
+            default_value,
+        })
+        .collect();
+    
+    EffectiveConfig {
+        entries,
+        manipulation_threshold: config.manipulation_threshold(),
+    }
+}
@@ -0,0 +1,121 @@
+use crate::types::*;
+use crate::storage::{ErrorLog, ModelStorage};
+use crate::model::VeriChainModel;
+use crate::preprocessing::{decode_image, preprocess_image, get_model_input_shape};
+use crate::handlers::analysis::apply_decision_policy;
+use ic_cdk::api::{instruction_counter, msg_caller, time};
+
+const DEFAULT_RECENT_ERRORS_LIMIT: u32 = 20;
+
+/// 4x4 RGB gradient PNG run through the pipeline by `self_test`.
+const SELF_TEST_PNG: [u8; 96] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x04,
+    0x08, 0x02, 0x00, 0x00, 0x00, 0x26, 0x93, 0x09, 0x29, 0x00, 0x00, 0x00,
+    0x27, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x15, 0x89, 0xc1, 0x11, 0x00,
+    0x30, 0x10, 0x44, 0x94, 0xb6, 0xa5, 0x29, 0x4d, 0x69, 0xc9, 0x79, 0x31,
+    0x00, 0x87, 0x62, 0x08, 0x73, 0xd3, 0xd9, 0x7e, 0x9c, 0x1c, 0xfd, 0x4d,
+    0xae, 0xd3, 0xf2, 0x01, 0xb6, 0x43, 0x14, 0x01, 0x30, 0x16, 0xff, 0xbb,
+    0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// Largest deviation from 1.0 accepted for the sum of class scores.
+const SCORE_SUM_TOLERANCE: f64 = 1e-3;
+
+pub fn handle_record_error(log: &mut ErrorLog, endpoint: &str, error_kind: &str, message: &str) {
+    log.push(ErrorEntry {
+        timestamp: time(),
+        caller: msg_caller().to_text(),
+        endpoint: endpoint.to_string(),
+        error_kind: error_kind.to_string(),
+        message: message.to_string(),
+    });
+}
+
+pub fn handle_get_recent_errors(log: &ErrorLog, limit: Option<u32>) -> Vec<ErrorEntry> {
+    log.recent(limit.unwrap_or(DEFAULT_RECENT_ERRORS_LIMIT) as usize)
+}
+
+/// Runs the embedded test image through decode, preprocess, inference and
+/// the decision policy, checking each stage's output rather than just that
+/// it returned. Input validation is skipped: the image is deliberately
+/// smaller than the minimum upload size.
+pub fn handle_self_test(
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+) -> SelfTestReport {
+    let mut stages = Vec::new();
+    let passed = run_self_test_stages(model, storage, config, &mut stages).is_ok();
+    SelfTestReport { passed, stages }
+}
+
+fn run_self_test_stages(
+    model: &VeriChainModel,
+    storage: &ModelStorage,
+    config: &CanisterConfig,
+    stages: &mut Vec<SelfTestStage>,
+) -> Result<(), ()> {
+    let image = run_stage(stages, "decode", || {
+        let image = decode_image(&SELF_TEST_PNG)?;
+        let detail = format!("{}x{} image", image.width(), image.height());
+        Ok((image, detail))
+    })?;
+    
+    let tensor = run_stage(stages, "preprocess", || {
+        let tensor = preprocess_image(image, &storage.get_normalization_stats())?;
+        let (channels, height, width) = get_model_input_shape();
+        let expected = (channels * height * width) as usize;
+        if tensor.len() != expected {
+            return Err(format!("tensor has {} values, expected {}", tensor.len(), expected));
+        }
+        if tensor.iter().any(|v| !v.is_finite()) {
+            return Err("tensor contains non-finite values".to_string());
+        }
+        let detail = format!("{} values", tensor.len());
+        Ok((tensor, detail))
+    })?;
+    
+    let mut prediction = run_stage(stages, "inference", || {
+        let prediction = model.predict_preprocessed(&tensor)?;
+        let scores = &prediction.raw_scores;
+        let sum = scores.real + scores.ai_generated + scores.deepfake;
+        if !sum.is_finite() || (sum - 1.0).abs() > SCORE_SUM_TOLERANCE {
+            return Err(format!("class scores sum to {}, expected 1.0", sum));
+        }
+        let detail = format!("scores sum to {:.4}", sum);
+        Ok((prediction, detail))
+    })?;
+    
+    run_stage(stages, "decision", || {
+        apply_decision_policy(&mut prediction, config);
+        if !(0.0..=1.0).contains(&prediction.raw_confidence) {
+            return Err(format!("confidence {} outside [0, 1]", prediction.raw_confidence));
+        }
+        let detail = format!("{:?} at {:.4} confidence", prediction.label, prediction.raw_confidence);
+        Ok(((), detail))
+    })
+}
+
+/// Runs one stage, recording its outcome and instruction cost.
+fn run_stage<T>(
+    stages: &mut Vec<SelfTestStage>,
+    stage: &str,
+    run: impl FnOnce() -> VeriChainResult<(T, String)>,
+) -> Result<T, ()> {
+    let start = instruction_counter();
+    let outcome = run();
+    let instructions = instruction_counter() - start;
+    
+    let (passed, detail, value) = match outcome {
+        Ok((value, detail)) => (true, detail, Some(value)),
+        Err(e) => (false, e, None),
+    };
+    stages.push(SelfTestStage {
+        stage: stage.to_string(),
+        passed,
+        instructions,
+        detail,
+    });
+    value.ok_or(())
+}
@@ -1,7 +1,15 @@
 pub mod analysis;
+pub mod analytics;
+pub mod config;
+pub mod diagnostics;
 pub mod model_management;
+pub mod moderation;
 pub mod system;
 
 pub use analysis::*;
+pub use analytics::*;
+pub use config::*;
+pub use diagnostics::*;
 pub use model_management::*;
+pub use moderation::*;
 pub use system::*;
@@ -1,23 +1,37 @@
 use crate::types::*;
 use crate::storage::ModelStorage;
-use crate::model::VeriChainModel;
-use crate::utils::{verify_chunk_integrity, validate_chunk_id, validate_batch_size};
+use crate::model::{VeriChainModel, MIN_MODEL_SIZE_BYTES, MAX_MODEL_SIZE_BYTES, MODEL_VERSION};
+use crate::utils::{
+    verify_chunk_integrity, validate_batch_size,
+    normalize_sha256_hex, validate_metadata_text, MAX_CHUNK_SIZE_BYTES,
+};
+use ic_cdk::api::instruction_counter;
+
+/// Largest whole-MiB chunk under `MAX_CHUNK_SIZE_BYTES`.
+const RECOMMENDED_CHUNK_SIZE_BYTES: u64 = 1024 * 1024;
 
 pub fn handle_upload_chunk(
     storage: &mut ModelStorage, 
     chunk_id: u32, 
     data: Vec<u8>, 
     expected_hash: String
-) -> VeriChainResult<String> {
+) -> ChunkUploadResult {
+    let expected_hash = match normalize_sha256_hex(&expected_hash) {
+        Ok(hash) => hash,
+        Err(e) => return ChunkUploadResult::failure(chunk_id, ChunkErrorKind::InvalidHash, e),
+    };
+    
     // Verify chunk integrity
     if !verify_chunk_integrity(&data, &expected_hash) {
-        return Err(format!("Chunk {} integrity verification failed", chunk_id));
+        return ChunkUploadResult::failure(
+            chunk_id,
+            ChunkErrorKind::HashMismatch,
+            format!("Chunk {} integrity verification failed", chunk_id),
+        );
     }
     
-    // Validate chunk ID if we have metadata
-    if let Some(metadata) = storage.get_metadata() {
-        validate_chunk_id(chunk_id, metadata.total_chunks)?;
-    }
+    // Resuming clients can tell a retried chunk from a new one
+    let is_reupload = storage.get_chunk(chunk_id).is_some();
     
     // Create and store chunk
     let chunk = ModelChunk {
@@ -26,9 +40,16 @@ pub fn handle_upload_chunk(
         hash: expected_hash,
     };
     
-    storage.store_chunk(chunk)?;
+    if let Err((error_kind, e)) = storage.store_chunk(chunk) {
+        return ChunkUploadResult::failure(chunk_id, error_kind, e);
+    }
     
-    Ok(format!("Chunk {} uploaded successfully", chunk_id))
+    let message = if is_reupload {
+        format!("Chunk {} re-uploaded (overwrote previous)", chunk_id)
+    } else {
+        format!("Chunk {} uploaded successfully", chunk_id)
+    };
+    ChunkUploadResult::success(chunk_id, message)
 }
 
 pub fn handle_upload_metadata(
@@ -38,12 +59,27 @@ pub fn handle_upload_metadata(
     total_chunks: u32,
     chunk_size_mb: u32,
 ) -> VeriChainResult<String> {
+    validate_metadata_text("original_file", &original_file)?;
+    
+    // Reject models that can't fit before any chunk is uploaded, instead of
+    // failing with StorageFull hundreds of chunks in. Only `original_size`
+    // is trustworthy here: `chunk_size_mb` is a rounded hint (setup.sh sends
+    // 1 for 0.8 MB chunks), so it can't bound the bytes the plan stores.
+    if original_size > MAX_MODEL_SIZE_BYTES as u64 {
+        return Err(format!(
+            "Storage full: a {} byte model exceeds the {} byte storage limit",
+            original_size, MAX_MODEL_SIZE_BYTES
+        ));
+    }
+    
     let metadata = ModelMetadata {
         original_file,
         original_size,
         total_chunks,
         chunk_size_mb: chunk_size_mb as f64,
-        version: "VeriChain-ViT-v1.0".to_string(),
+        version: MODEL_VERSION.to_string(),
+        // Re-uploading metadata keeps previously configured statistics
+        normalization: storage.get_metadata().and_then(|m| m.normalization.clone()),
     };
     
     storage.store_metadata(metadata)?;
@@ -51,6 +87,135 @@ pub fn handle_upload_metadata(
     Ok("Metadata uploaded successfully".to_string())
 }
 
+pub fn handle_validate_upload_plan(
+    metadata: ModelMetadata,
+    chunk_hashes: Vec<String>,
+) -> VeriChainResult<UploadPlanReport> {
+    let mut issues = Vec::new();
+    
+    let original_size = metadata.original_size;
+    if original_size < MIN_MODEL_SIZE_BYTES as u64 || original_size > MAX_MODEL_SIZE_BYTES as u64 {
+        issues.push(format!(
+            "original_size {} bytes is outside the accepted model range {}-{} bytes",
+            original_size, MIN_MODEL_SIZE_BYTES, MAX_MODEL_SIZE_BYTES
+        ));
+    }
+    
+    if let Err(e) = ModelVersion::parse(&metadata.version) {
+        issues.push(e);
+    }
+    
+    // Same rounding as tools/model_chunker.py: int(chunk_size_mb * 1024 * 1024)
+    let chunk_size_bytes = if metadata.chunk_size_mb.is_finite() && metadata.chunk_size_mb > 0.0 {
+        (metadata.chunk_size_mb * 1024.0 * 1024.0) as u64
+    } else {
+        0
+    };
+    
+    if chunk_size_bytes == 0 {
+        issues.push(format!("chunk_size_mb must be positive, got {}", metadata.chunk_size_mb));
+    } else if chunk_size_bytes > MAX_CHUNK_SIZE_BYTES {
+        issues.push(format!(
+            "chunk size {} bytes exceeds the {} byte ingress-safe maximum",
+            chunk_size_bytes, MAX_CHUNK_SIZE_BYTES
+        ));
+    }
+    
+    let expected_chunks = if chunk_size_bytes > 0 {
+        original_size.div_ceil(chunk_size_bytes)
+    } else {
+        0
+    };
+    
+    if metadata.total_chunks == 0 {
+        issues.push("total_chunks must be greater than zero".to_string());
+    } else if chunk_size_bytes > 0 && expected_chunks != metadata.total_chunks as u64 {
+        issues.push(format!(
+            "total_chunks is {} but {} bytes split into {} byte chunks gives {}",
+            metadata.total_chunks, original_size, chunk_size_bytes, expected_chunks
+        ));
+    }
+    
+    if !chunk_hashes.is_empty() {
+        if chunk_hashes.len() != metadata.total_chunks as usize {
+            issues.push(format!(
+                "{} chunk hashes supplied for {} chunks",
+                chunk_hashes.len(), metadata.total_chunks
+            ));
+        }
+        
+        let malformed: Vec<usize> = chunk_hashes.iter()
+            .enumerate()
+            .filter(|(_, hash)| normalize_sha256_hex(hash).is_err())
+            .map(|(i, _)| i)
+            .collect();
+        if !malformed.is_empty() {
+            issues.push(format!(
+                "chunk hashes must be 64 hex characters; malformed at indices {:?}",
+                malformed
+            ));
+        }
+    }
+    
+    if !issues.is_empty() {
+        return Err(format!("Invalid upload plan: {}", issues.join("; ")));
+    }
+    
+    Ok(build_upload_plan(original_size, chunk_size_bytes, metadata.total_chunks))
+}
+
+/// Splits a model of `original_size` bytes into whole-MiB chunks, matching the
+/// integer `chunk_size_mb` that `upload_model_metadata` accepts.
+pub fn handle_recommend_chunk_plan(original_size: u64) -> VeriChainResult<UploadPlanReport> {
+    if original_size < MIN_MODEL_SIZE_BYTES as u64 || original_size > MAX_MODEL_SIZE_BYTES as u64 {
+        return Err(format!(
+            "original_size {} bytes is outside the accepted model range {}-{} bytes",
+            original_size, MIN_MODEL_SIZE_BYTES, MAX_MODEL_SIZE_BYTES
+        ));
+    }
+    
+    let total_chunks = original_size.div_ceil(RECOMMENDED_CHUNK_SIZE_BYTES) as u32;
+    Ok(build_upload_plan(original_size, RECOMMENDED_CHUNK_SIZE_BYTES, total_chunks))
+}
+
+fn build_upload_plan(original_size: u64, chunk_size_bytes: u64, total_chunks: u32) -> UploadPlanReport {
+    let last_chunk_size_bytes = original_size - (total_chunks as u64 - 1) * chunk_size_bytes;
+    let init_calls = total_chunks.div_ceil(calculate_optimal_batch_size(total_chunks));
+    
+    UploadPlanReport {
+        expected_chunks: total_chunks,
+        chunk_size_bytes,
+        last_chunk_size_bytes,
+        // One metadata call, one call per chunk, then the initialization batches
+        total_ingress_calls: 1 + total_chunks + init_calls,
+    }
+}
+
+/// Run before initialization to catch storage left incoherent by an
+/// interrupted upload or upgrade, which otherwise only shows up as a
+/// missing-chunk error partway through assembly.
+pub fn handle_check_storage_integrity(storage: &ModelStorage) -> StorageIntegrityReport {
+    let metadata = storage.get_metadata();
+    let missing_ids = storage.get_missing_chunks();
+    let unexpected_ids = storage.get_unexpected_chunks();
+    let stored_bytes = storage.stored_bytes();
+    let declared_bytes = metadata.map_or(0, |m| m.original_size);
+    
+    StorageIntegrityReport {
+        metadata_present: metadata.is_some(),
+        chunks_in_map: storage.chunk_count(),
+        chunks_declared: metadata.map_or(0, |m| m.total_chunks),
+        consistent: metadata.is_some()
+            && missing_ids.is_empty()
+            && unexpected_ids.is_empty()
+            && stored_bytes == declared_bytes,
+        missing_ids,
+        unexpected_ids,
+        stored_bytes,
+        declared_bytes,
+    }
+}
+
 pub fn handle_get_upload_status(storage: &ModelStorage) -> UploadStatus {
     let (total_chunks, uploaded_chunks, missing_chunks, is_complete, original_size_mb) = 
         storage.get_upload_stats();
@@ -64,10 +229,28 @@ pub fn handle_get_upload_status(storage: &ModelStorage) -> UploadStatus {
     }
 }
 
+pub fn handle_get_model_chunk_data(storage: &ModelStorage, chunk_id: u32) -> Option<Vec<u8>> {
+    storage.get_chunk(chunk_id).map(|chunk| chunk.data.clone())
+}
+
 pub fn handle_initialize_model(
     storage: &mut ModelStorage,
     model: &mut VeriChainModel,
+    config: &CanisterConfig,
 ) -> VeriChainResult<String> {
+    // Assembled bytes are kept in storage, so an unloaded model can be
+    // reloaded without re-running chunk assembly. A retry after success is
+    // a no-op so at-least-once callers don't see spurious failures.
+    if storage.is_initialized() {
+        if model.is_loaded() {
+            return Ok("Model already initialized".to_string());
+        }
+        let model_data = storage.get_model_data()
+            .ok_or("Storage is initialized but holds no model data")?;
+        load_approved_model(model, model_data, config)?;
+        return Ok("Model reloaded from assembled storage".to_string());
+    }
+    
     // Check if upload is complete
     if !storage.is_upload_complete() {
         let missing = storage.get_missing_chunks();
@@ -78,17 +261,16 @@ pub fn handle_initialize_model(
     // Start initialization
     storage.start_initialization()?;
     
-    // Determine optimal batch size based on total chunks
-    let total_chunks = storage.get_initialization_progress().1;
-    let batch_size = calculate_optimal_batch_size(total_chunks);
+    // No cost measurement yet, so the first batch uses the static size
+    let batch_size = next_batch_size(storage, config);
     
     // Process first batch
-    let processed = storage.process_chunks_batch(batch_size)?;
+    let processed = process_measured_batch(storage, batch_size)?;
     
     // If all chunks processed in one go, load the model
     if storage.is_initialized() {
         if let Some(model_data) = storage.get_model_data() {
-            model.load_from_bytes(model_data)?;
+            load_approved_model(model, model_data, config)?;
             return Ok("Model initialized and loaded successfully".to_string());
         }
     }
@@ -96,26 +278,48 @@ pub fn handle_initialize_model(
     Ok(format!("Initialization started. Processed {} chunks in first batch", processed))
 }
 
+/// Loads the assembled model, then unloads it again if its hash is not in
+/// the configured allow-list. Checking after the load reuses the hash it
+/// computes instead of hashing the whole model twice. Every path that loads
+/// a model goes through here, including the reload after an upgrade.
+pub fn load_approved_model(model: &mut VeriChainModel, model_data: &[u8], config: &CanisterConfig) -> VeriChainResult<()> {
+    model.load_from_bytes(model_data)?;
+    
+    let model_hash = model.get_model_hash().unwrap_or_default();
+    if !config.is_model_approved(&model_hash) {
+        model.unload();
+        return Err(format!(
+            "Model hash {} is not in approved_model_hashes; model was not loaded",
+            model_hash
+        ));
+    }
+    
+    Ok(())
+}
+
 pub fn handle_continue_initialization(
     storage: &mut ModelStorage,
     model: &mut VeriChainModel,
+    config: &CanisterConfig,
     batch_size: Option<u32>,
 ) -> VeriChainResult<String> {
     if storage.is_initialized() {
-        return Err("Model already initialized".to_string());
+        if model.is_loaded() {
+            return Ok("Model already initialized".to_string());
+        }
+        return Err("Model is assembled but not loaded. Call initialize_model to load it.".to_string());
     }
     
-    let total_chunks = storage.get_initialization_progress().1;
-    let batch_size = batch_size.unwrap_or_else(|| calculate_optimal_batch_size(total_chunks));
+    let batch_size = batch_size.unwrap_or_else(|| next_batch_size(storage, config));
     
     validate_batch_size(batch_size)?;
     
-    let processed = storage.process_chunks_batch(batch_size)?;
+    let processed = process_measured_batch(storage, batch_size)?;
     
     // Check if initialization is complete
     if storage.is_initialized() {
         if let Some(model_data) = storage.get_model_data() {
-            model.load_from_bytes(model_data)?;
+            load_approved_model(model, model_data, config)?;
             return Ok(format!("Model initialization completed! Processed {} chunks in final batch", processed));
         }
     }
@@ -124,7 +328,30 @@ pub fn handle_continue_initialization(
     Ok(format!("Processed {} chunks. Progress: {}/{}", processed, current, total))
 }
 
-pub fn handle_get_initialization_status(storage: &ModelStorage) -> InitializationStatus {
+pub fn handle_set_normalization_stats(storage: &mut ModelStorage, stats: NormalizationStats) -> VeriChainResult<String> {
+    stats.validate()?;
+    storage.set_normalization_stats(stats)?;
+    Ok("Normalization statistics updated".to_string())
+}
+
+pub fn handle_get_normalization_stats(storage: &ModelStorage) -> NormalizationStats {
+    storage.get_normalization_stats()
+}
+
+/// Drops the loaded model copy to free heap. Verification is cleared too, so
+/// a reloaded model must be verified again.
+pub fn handle_unload_model(model: &mut VeriChainModel) -> VeriChainResult<String> {
+    if !model.is_loaded() {
+        return Err("Model is not loaded".to_string());
+    }
+    
+    let freed_mb = model.model_size_bytes() as f64 / (1024.0 * 1024.0);
+    model.unload();
+    
+    Ok(format!("Model unloaded, freed {:.2}MB. Call initialize_model to reload it.", freed_mb))
+}
+
+pub fn handle_get_initialization_status(storage: &ModelStorage, config: &CanisterConfig) -> InitializationStatus {
     let (processed_chunks, total_chunks) = storage.get_initialization_progress();
     
     InitializationStatus {
@@ -135,6 +362,26 @@ pub fn handle_get_initialization_status(storage: &ModelStorage) -> Initializatio
         current_size_mb: storage.get_current_size_mb(),
         estimated_total_size_mb: storage.get_estimated_total_size_mb(),
         error_message: None,
+        next_batch_size: next_batch_size(storage, config),
+        instructions_per_chunk: storage.get_instructions_per_chunk(),
+    }
+}
+
+/// Assembles one batch and records its per-chunk instruction cost.
+fn process_measured_batch(storage: &mut ModelStorage, batch_size: u32) -> VeriChainResult<u32> {
+    let before = instruction_counter();
+    let processed = storage.process_chunks_batch(batch_size)?;
+    storage.record_batch_cost(processed, instruction_counter().saturating_sub(before));
+    Ok(processed)
+}
+
+/// Sizes the next batch to fit the configured instruction budget once a
+/// batch has been measured, and falls back to the static sizes before that.
+fn next_batch_size(storage: &ModelStorage, config: &CanisterConfig) -> u32 {
+    let budget = config.init_batch_instruction_budget();
+    match storage.get_instructions_per_chunk() {
+        Some(cost) if budget > 0 && cost > 0 => (budget / cost).clamp(1, 200) as u32,
+        _ => calculate_optimal_batch_size(storage.get_initialization_progress().1),
     }
 }
 
@@ -145,3 +392,43 @@ fn calculate_optimal_batch_size(total_chunks: u32) -> u32 {
         _ => 100,
     }
 }
+
+pub fn handle_verify_loaded_model(model: &mut VeriChainModel, expected_hash: String) -> VeriChainResult<String> {
+    model.verify_hash(&expected_hash)?;
+    Ok(format!("Loaded model verified against hash {}", expected_hash.trim().to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The plan shipped in assets/model_metadata.json, as setup.sh sends it:
+    /// 410 chunks of 0.8 MB with the chunk size rounded up to 1.
+    #[test]
+    fn metadata_accepts_shipped_plan_with_rounded_chunk_size() {
+        let mut storage = ModelStorage::new();
+        let result = handle_upload_metadata(
+            &mut storage,
+            "verichain-model.onnx".to_string(),
+            343_480_839,
+            410,
+            1,
+        );
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(storage.get_metadata().map(|m| m.total_chunks), Some(410));
+    }
+
+    #[test]
+    fn metadata_rejects_model_larger_than_storage_limit() {
+        let mut storage = ModelStorage::new();
+        let result = handle_upload_metadata(
+            &mut storage,
+            "huge.onnx".to_string(),
+            MAX_MODEL_SIZE_BYTES as u64 + 1,
+            500,
+            1,
+        );
+        assert!(result.unwrap_err().starts_with("Storage full"));
+        assert!(storage.get_metadata().is_none());
+    }
+}
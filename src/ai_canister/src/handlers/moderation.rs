@@ -0,0 +1,34 @@
+use crate::types::*;
+use crate::storage::QuarantineList;
+use crate::utils::{normalize_sha256_hex, validate_metadata_text};
+use ic_cdk::api::time;
+
+pub fn handle_quarantine_hash(
+    quarantine: &mut QuarantineList,
+    hash: String,
+    reason: String,
+) -> VeriChainResult<String> {
+    let hash = normalize_sha256_hex(&hash)?;
+    validate_metadata_text("reason", &reason)?;
+    
+    quarantine.insert(QuarantineEntry {
+        hash: hash.clone(),
+        reason,
+        quarantined_at: time(),
+    });
+    
+    Ok(format!("Content {} quarantined", hash))
+}
+
+pub fn handle_unquarantine_hash(quarantine: &mut QuarantineList, hash: String) -> VeriChainResult<String> {
+    let hash = normalize_sha256_hex(&hash)?;
+    
+    match quarantine.remove(&hash) {
+        Some(_) => Ok(format!("Content {} removed from quarantine", hash)),
+        None => Err(format!("Content {} is not quarantined", hash)),
+    }
+}
+
+pub fn handle_list_quarantined(quarantine: &QuarantineList) -> Vec<QuarantineEntry> {
+    quarantine.list()
+}
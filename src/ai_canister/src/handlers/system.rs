@@ -1,22 +1,36 @@
 use crate::types::*;
-use crate::model::VeriChainModel;
+use crate::model::{VeriChainModel, MODEL_VERSION};
 use ic_cdk::api::time;
 
 pub fn handle_get_model_info(model: &VeriChainModel) -> ModelInfo {
     let (_channels, height, width) = model.get_input_shape();
     
     ModelInfo {
-        version: "VeriChain-ViT-v1.0".to_string(),
+        version: MODEL_VERSION.to_string(),
         input_size: (width, height),
         supported_formats: model.get_supported_formats(),
         model_loaded: model.is_loaded(),
-        total_parameters: Some(85_800_000), // Approximate ViT parameter count
+        total_parameters: model.parameter_count(),
+        parsed_version: ModelVersion::parse(MODEL_VERSION).ok(),
     }
 }
 
-pub fn handle_health_check(model: &VeriChainModel, start_time: u64) -> SystemHealth {
+/// Classes the model scores, in logit order, so clients know how many
+/// categories to render.
+pub fn handle_get_model_classes() -> Vec<String> {
+    CLASS_LABELS.iter().map(|c| c.to_string()).collect()
+}
+
+pub fn handle_health_check(
+    model: &VeriChainModel,
+    install_time: u64,
+    start_time: u64,
+    last_upgrade_error: Option<String>,
+) -> SystemHealth {
     let current_time = time();
-    let uptime_seconds = (current_time - start_time) / 1_000_000_000; // Convert to seconds
+    // Convert to seconds
+    let uptime_seconds = current_time.saturating_sub(install_time) / 1_000_000_000;
+    let current_version_uptime_seconds = current_time.saturating_sub(start_time) / 1_000_000_000;
     let cycle_balance = ic_cdk::api::canister_cycle_balance() as u64;
     
     // Estimate memory usage (rough calculation)
@@ -26,17 +40,25 @@ pub fn handle_health_check(model: &VeriChainModel, start_time: u64) -> SystemHea
         50.0 // Base canister overhead
     };
     
-    let status = if model.is_loaded() {
-        "healthy".to_string()
-    } else {
-        "model_not_loaded".to_string()
+    let inference_mode = model.inference_mode();
+    let status = match (model.is_loaded(), inference_mode, model.is_verified()) {
+        (false, _, _) => "model_not_loaded".to_string(),
+        (true, "onnx_real", true) => "healthy".to_string(),
+        (true, "onnx_real", false) => "loaded_unverified".to_string(),
+        // Anything short of a real ONNX forward pass, including the
+        // simplified weight-sampling path
+        (true, _, _) => "degraded".to_string(),
     };
     
     SystemHealth {
         status,
         model_loaded: model.is_loaded(),
+        model_verified: model.is_verified(),
+        inference_mode: inference_mode.to_string(),
         uptime_seconds,
+        current_version_uptime_seconds,
         memory_usage_mb,
         cycle_balance,
+        last_upgrade_error,
     }
 }
@@ -7,10 +7,11 @@ mod utils;
 mod preprocessing;
 mod storage;
 mod model;
+mod forensics;
 mod handlers;
 
 use types::*;
-use storage::ModelStorage;
+use storage::{ModelStorage, UpgradeState, UpgradeStateRef, AnalyticsState, QuarantineList, ErrorLog, ConfidenceHistory};
 use model::VeriChainModel;
 use handlers::*;
 
@@ -19,97 +20,411 @@ thread_local! {
     static VERICHAIN_MODEL: RefCell<VeriChainModel> = RefCell::new(
         VeriChainModel::new().expect("Failed to initialize VeriChain model")
     );
+    // Time of the running code's install or upgrade
     static START_TIME: RefCell<u64> = RefCell::new(0);
+    // Time of the original install, carried across upgrades
+    static INSTALL_TIME: RefCell<u64> = RefCell::new(0);
+    static CONFIG: RefCell<CanisterConfig> = RefCell::new(CanisterConfig::default());
+    static ANALYTICS: RefCell<AnalyticsState> = RefCell::new(AnalyticsState::new());
+    static QUARANTINE: RefCell<QuarantineList> = RefCell::new(QuarantineList::new());
+    static ERROR_LOG: RefCell<ErrorLog> = RefCell::new(ErrorLog::new());
+    // Why the last upgrade couldn't restore everything, if it couldn't
+    static LAST_UPGRADE_ERROR: RefCell<Option<String>> = RefCell::new(None);
+    static CONFIDENCE_HISTORY: RefCell<ConfidenceHistory> = RefCell::new(ConfidenceHistory::new());
 }
 
 #[init]
 fn init() {
+    let now = time();
     START_TIME.with(|t| {
-        *t.borrow_mut() = time();
+        *t.borrow_mut() = now;
+    });
+    INSTALL_TIME.with(|t| {
+        *t.borrow_mut() = now;
     });
 }
 
 #[pre_upgrade]
 fn pre_upgrade() {
-    // Store model storage state
     MODEL_STORAGE.with(|storage| {
-        let storage_ref = storage.borrow();
-        ic_cdk::storage::stable_save((storage_ref.clone(),)).expect("Failed to save model storage state");
-    });
-    
-    // Store model state if loaded
-    VERICHAIN_MODEL.with(|model| {
-        let model_ref = model.borrow();
-        if model_ref.is_loaded() {
-            // Save model data and state
-            let model_state = (
-                model_ref.get_model_data(),
-                model_ref.get_model_hash(),
-                model_ref.is_loaded(),
-            );
-            ic_cdk::storage::stable_save(model_state).expect("Failed to save model state");
-        }
+        VERICHAIN_MODEL.with(|model| {
+            CONFIG.with(|config| {
+                ANALYTICS.with(|analytics| {
+                    QUARANTINE.with(|quarantine| {
+                        let storage_ref = storage.borrow();
+                        let model_ref = model.borrow();
+                        let model_loaded = model_ref.is_loaded();
+                        
+                        // Save model data and state only if loaded. The model
+                        // assembled in storage is normally the same bytes, so
+                        // it's only written again when it differs.
+                        let model_data = model_ref.get_model_data()
+                            .filter(|_| model_loaded)
+                            .filter(|data| storage_ref.get_model_data() != Some(*data));
+                        
+                        let config_ref = config.borrow();
+                        let analytics_ref = analytics.borrow();
+                        let quarantine_ref = quarantine.borrow();
+                        let state = UpgradeStateRef {
+                            model_storage: &*storage_ref,
+                            model_data,
+                            model_hash: if model_loaded { model_ref.get_model_hash() } else { None },
+                            model_loaded,
+                            verified_model_hash: model_ref.get_verified_hash(),
+                            config: Some(&*config_ref),
+                            analytics: Some(&*analytics_ref),
+                            quarantine: Some(&*quarantine_ref),
+                            install_time: Some(INSTALL_TIME.with(|t| *t.borrow())),
+                        };
+                        
+                        ic_cdk::storage::stable_save((state,)).expect("Failed to save canister state");
+                    })
+                })
+            })
+        })
     });
     
-    ic_cdk::println!("Pre-upgrade: Saved model storage and model state");
+    ic_cdk::println!("Pre-upgrade: Saved model storage, model state, configuration, analytics and quarantine list");
 }
 
 #[post_upgrade]
 fn post_upgrade() {
+    let now = time();
     START_TIME.with(|t| {
-        *t.borrow_mut() = time();
+        *t.borrow_mut() = now;
+    });
+    // Best guess until the saved install time is restored below
+    INSTALL_TIME.with(|t| {
+        *t.borrow_mut() = now;
     });
     
+    let state = match ic_cdk::storage::stable_restore::<(UpgradeState,)>() {
+        Ok((state,)) => state,
+        Err(e) => match UpgradeState::restore_legacy() {
+            Some(state) => {
+                ic_cdk::println!("Post-upgrade: Restored state saved in the legacy layout");
+                state
+            }
+            None => {
+                ic_cdk::println!("Post-upgrade: Failed to restore canister state: {:?}", e);
+                record_upgrade_error(format!("Failed to restore canister state: {:?}", e));
+                return;
+            }
+        },
+    };
+    
     // Restore model storage state
-    match ic_cdk::storage::stable_restore::<(ModelStorage,)>() {
-        Ok((restored_storage,)) => {
-            MODEL_STORAGE.with(|storage| {
-                *storage.borrow_mut() = restored_storage;
-            });
-            ic_cdk::println!("Post-upgrade: Restored model storage state");
-        }
-        Err(e) => {
-            ic_cdk::println!("Post-upgrade: Failed to restore model storage: {:?}", e);
-        }
+    MODEL_STORAGE.with(|storage| {
+        *storage.borrow_mut() = state.model_storage;
+    });
+    ic_cdk::println!("Post-upgrade: Restored model storage state");
+    
+    if let Some(saved) = state.config {
+        CONFIG.with(|config| {
+            *config.borrow_mut() = saved;
+        });
+    }
+    
+    if let Some(saved) = state.analytics {
+        ANALYTICS.with(|analytics| {
+            *analytics.borrow_mut() = saved;
+        });
+    }
+    
+    // A missing, zero or future install time can't be trusted; keep `now`
+    if let Some(install_time) = state.install_time.filter(|t| *t > 0 && *t <= now) {
+        INSTALL_TIME.with(|t| {
+            *t.borrow_mut() = install_time;
+        });
+    }
+    
+    if let Some(saved) = state.quarantine {
+        QUARANTINE.with(|quarantine| {
+            *quarantine.borrow_mut() = saved;
+        });
     }
     
-    // Restore model state if available
-    match ic_cdk::storage::stable_restore::<(Option<Vec<u8>>, Option<String>, bool)>() {
-        Ok((model_data, _model_hash, was_loaded)) => {
-            if was_loaded && model_data.is_some() {
-                VERICHAIN_MODEL.with(|model| {
-                    let mut model_ref = model.borrow_mut();
-                    if let Some(data) = model_data {
-                        match model_ref.load_from_bytes(&data) {
-                            Ok(_) => {
-                                ic_cdk::println!("Post-upgrade: Successfully restored loaded model");
-                            }
-                            Err(e) => {
-                                ic_cdk::println!("Post-upgrade: Failed to reload model: {}", e);
-                            }
+    // Restore model state if available, falling back to the model assembled
+    // in model storage when the saved copy won't load. The saved copy is
+    // omitted when it matched the assembled model.
+    if state.model_loaded {
+        VERICHAIN_MODEL.with(|model| {
+            let mut model_ref = model.borrow_mut();
+            let config_ref = CONFIG.with(|config| config.borrow().clone());
+            let from_storage = |model_ref: &mut VeriChainModel| {
+                MODEL_STORAGE.with(|storage| match storage.borrow().get_model_data() {
+                    Some(data) => load_approved_model(model_ref, data, &config_ref),
+                    None => Err("no assembled model in storage".to_string()),
+                })
+            };
+            let restored = match &state.model_data {
+                Some(data) => load_approved_model(&mut *model_ref, data, &config_ref).or_else(|e| {
+                    ic_cdk::println!("Post-upgrade: Failed to reload model: {}; retrying from stored chunks", e);
+                    from_storage(&mut *model_ref)
+                        .map_err(|fallback| format!("Failed to reload model: {}; fallback from stored chunks: {}", e, fallback))
+                }),
+                None => from_storage(&mut *model_ref)
+                    .map_err(|e| format!("Failed to reload model from stored chunks: {}", e)),
+            };
+            
+            match restored {
+                Ok(_) => {
+                    ic_cdk::println!("Post-upgrade: Successfully restored loaded model");
+                    if let Some(hash) = &state.verified_model_hash {
+                        if let Err(e) = model_ref.verify_hash(hash) {
+                            ic_cdk::println!("Post-upgrade: Model no longer verified: {}", e);
                         }
                     }
-                });
+                }
+                Err(e) => {
+                    ic_cdk::println!("Post-upgrade: {}", e);
+                    record_upgrade_error(e);
+                }
             }
-        }
-        Err(e) => {
-            ic_cdk::println!("Post-upgrade: Failed to restore model state: {:?}", e);
-        }
+        });
     }
 }
 
+fn record_upgrade_error(error: String) {
+    LAST_UPGRADE_ERROR.with(|last| {
+        *last.borrow_mut() = Some(error);
+    });
+}
+
 // Core analysis function
 #[update]
 fn analyze(image_data: Vec<u8>) -> VeriChainResult<MediaAnalysisResult> {
-    VERICHAIN_MODEL.with(|model| {
-        let model_ref = model.borrow();
-        handle_analyze(image_data, &*model_ref)
+    run_analysis(image_data)
+}
+
+// Same analysis, returned as a Candid-encoded `Result<MediaAnalysisResult, String>` blob
+#[update]
+fn analyze_encoded(image_data: Vec<u8>) -> Vec<u8> {
+    let result = run_analysis(image_data);
+    handle_encode_analysis(&result).unwrap_or_else(ic_cdk::trap)
+}
+
+// Same analysis as a fixed 16-byte record; see `handle_pack_analysis` for the layout
+#[update]
+fn analyze_packed(image_data: Vec<u8>) -> Vec<u8> {
+    let result = run_analysis(image_data);
+    handle_pack_analysis(&result).to_vec()
+}
+
+#[update]
+fn analyze_robust(image_data: Vec<u8>) -> VeriChainResult<RobustResult> {
+    let format = utils::detect_image_format(&image_data);
+    let error_kind = classify_failure(&image_data);
+    let result = VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                let model_ref = model.borrow();
+                let storage_ref = storage.borrow();
+                let config_ref = config.borrow();
+                handle_analyze_robust(image_data, &*model_ref, &*storage_ref, &*config_ref)
+            })
+        })
+    });
+    record_instructions();
+    
+    match &result {
+        Ok(_) => record_analysis(format, None),
+        Err(e) => record_error("analyze_robust", error_kind, e),
+    }
+    result
+}
+
+#[update]
+fn analyze_threshold_sweep(image_data: Vec<u8>, thresholds: Vec<f64>) -> VeriChainResult<SweepResult> {
+    let format = utils::detect_image_format(&image_data);
+    let error_kind = classify_failure(&image_data);
+    let result = VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                let model_ref = model.borrow();
+                let storage_ref = storage.borrow();
+                let config_ref = config.borrow();
+                handle_analyze_threshold_sweep(image_data, thresholds, &*model_ref, &*storage_ref, &*config_ref)
+            })
+        })
+    });
+    record_instructions();
+    
+    match &result {
+        Ok(sweep) => record_analysis(format, Some(&sweep.prediction)),
+        Err(e) => record_error("analyze_threshold_sweep", error_kind, e),
+    }
+    result
+}
+
+#[update]
+fn verify_against_reference(candidate: Vec<u8>, reference: Vec<u8>) -> VeriChainResult<ReferenceMatchResult> {
+    let error_kind = match classify_failure(&candidate) {
+        "analysis_failed" => classify_failure(&reference),
+        invalid => invalid,
+    };
+    let result = VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                let model_ref = model.borrow();
+                let storage_ref = storage.borrow();
+                let config_ref = config.borrow();
+                handle_verify_against_reference(candidate, reference, &*model_ref, &*storage_ref, &*config_ref)
+            })
+        })
+    });
+    record_instructions();
+    
+    if let Err(e) = &result {
+        record_error("verify_against_reference", error_kind, e);
+    }
+    result
+}
+
+fn run_analysis(image_data: Vec<u8>) -> VeriChainResult<MediaAnalysisResult> {
+    let format = utils::detect_image_format(&image_data);
+    let error_kind = classify_failure(&image_data);
+    let result = VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                QUARANTINE.with(|quarantine| {
+                    let model_ref = model.borrow();
+                    let storage_ref = storage.borrow();
+                    let config_ref = config.borrow();
+                    let quarantine_ref = quarantine.borrow();
+                    handle_analyze(image_data, &*model_ref, &*storage_ref, &*config_ref, &*quarantine_ref)
+                })
+            })
+        })
+    });
+    record_instructions();
+    
+    match &result {
+        Ok(analysis) => record_analysis(format, Some(&analysis.prediction)),
+        Err(e) => record_error("analyze", error_kind, e),
+    }
+    result
+}
+
+// Charges the whole message's instructions, failed analyses included, to the
+// lifetime total
+fn record_instructions() {
+    ANALYTICS.with(|analytics| {
+        let mut analytics_ref = analytics.borrow_mut();
+        handle_record_instructions(&mut *analytics_ref);
+    });
+}
+
+fn record_analysis(format: Option<&str>, prediction: Option<&PredictionResult>) {
+    ANALYTICS.with(|analytics| {
+        CONFIG.with(|config| {
+            let mut analytics_ref = analytics.borrow_mut();
+            let config_ref = config.borrow();
+            handle_record_analysis(&mut *analytics_ref, format, prediction, &*config_ref);
+        })
+    });
+    
+    if let Some(prediction) = prediction {
+        CONFIDENCE_HISTORY.with(|history| {
+            let mut history_ref = history.borrow_mut();
+            handle_record_confidence(&mut *history_ref, prediction);
+        });
+    }
+}
+
+// Decided before the input is consumed: a failure on input that passes
+// validation happened during readiness checks or inference
+fn classify_failure(image_data: &[u8]) -> &'static str {
+    if utils::validate_image_data(image_data).is_ok() {
+        "analysis_failed"
+    } else {
+        "invalid_input"
+    }
+}
+
+fn record_error(endpoint: &str, error_kind: &str, message: &str) {
+    ERROR_LOG.with(|log| {
+        let mut log_ref = log.borrow_mut();
+        handle_record_error(&mut *log_ref, endpoint, error_kind, message);
+    });
+}
+
+// Configuration
+#[update]
+fn update_config(new_config: CanisterConfig) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    CONFIG.with(|config| {
+        VERICHAIN_MODEL.with(|model| {
+            let mut config_ref = config.borrow_mut();
+            let mut model_ref = model.borrow_mut();
+            handle_update_config(&mut *config_ref, &mut *model_ref, new_config)
+        })
+    })
+}
+
+#[update]
+fn set_confidence_threshold(threshold: f64) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    CONFIG.with(|config| {
+        let mut config_ref = config.borrow_mut();
+        handle_set_confidence_threshold(&mut *config_ref, threshold)
+    })
+}
+
+#[query]
+fn get_confidence_threshold() -> f64 {
+    CONFIG.with(|config| config.borrow().base_threshold())
+}
+
+#[query]
+fn get_config() -> CanisterConfig {
+    CONFIG.with(|config| config.borrow().clone())
+}
+
+#[query]
+fn get_effective_config() -> EffectiveConfig {
+    CONFIG.with(|config| {
+        let config_ref = config.borrow();
+        handle_get_effective_config(&*config_ref)
+    })
+}
+
+// Moderation (controller-only)
+#[update]
+fn quarantine_hash(hash: String, reason: String) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine_ref = quarantine.borrow_mut();
+        handle_quarantine_hash(&mut *quarantine_ref, hash, reason)
+    })
+}
+
+#[update]
+fn unquarantine_hash(hash: String) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine_ref = quarantine.borrow_mut();
+        handle_unquarantine_hash(&mut *quarantine_ref, hash)
+    })
+}
+
+#[query]
+fn list_quarantined() -> VeriChainResult<Vec<QuarantineEntry>> {
+    utils::require_controller()?;
+    
+    QUARANTINE.with(|quarantine| {
+        let quarantine_ref = quarantine.borrow();
+        Ok(handle_list_quarantined(&*quarantine_ref))
     })
 }
 
 // Model management functions
 #[update]
-fn upload_model_chunk(chunk_id: u32, data: Vec<u8>, hash: String) -> VeriChainResult<String> {
+fn upload_model_chunk(chunk_id: u32, data: Vec<u8>, hash: String) -> ChunkUploadResult {
     MODEL_STORAGE.with(|storage| {
         let mut storage_ref = storage.borrow_mut();
         handle_upload_chunk(&mut *storage_ref, chunk_id, data, hash)
@@ -133,9 +448,12 @@ fn upload_model_metadata(
 fn initialize_model() -> VeriChainResult<String> {
     MODEL_STORAGE.with(|storage| {
         VERICHAIN_MODEL.with(|model| {
-            let mut storage_ref = storage.borrow_mut();
-            let mut model_ref = model.borrow_mut();
-            handle_initialize_model(&mut *storage_ref, &mut *model_ref)
+            CONFIG.with(|config| {
+                let mut storage_ref = storage.borrow_mut();
+                let mut model_ref = model.borrow_mut();
+                let config_ref = config.borrow();
+                handle_initialize_model(&mut *storage_ref, &mut *model_ref, &*config_ref)
+            })
         })
     })
 }
@@ -144,14 +462,65 @@ fn initialize_model() -> VeriChainResult<String> {
 fn continue_initialization(batch_size: Option<u32>) -> VeriChainResult<String> {
     MODEL_STORAGE.with(|storage| {
         VERICHAIN_MODEL.with(|model| {
-            let mut storage_ref = storage.borrow_mut();
-            let mut model_ref = model.borrow_mut();
-            handle_continue_initialization(&mut *storage_ref, &mut *model_ref, batch_size)
+            CONFIG.with(|config| {
+                let mut storage_ref = storage.borrow_mut();
+                let mut model_ref = model.borrow_mut();
+                let config_ref = config.borrow();
+                handle_continue_initialization(&mut *storage_ref, &mut *model_ref, &*config_ref, batch_size)
+            })
         })
     })
 }
 
+#[update]
+fn verify_loaded_model(expected_hash: String) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    VERICHAIN_MODEL.with(|model| {
+        let mut model_ref = model.borrow_mut();
+        handle_verify_loaded_model(&mut *model_ref, expected_hash)
+    })
+}
+
+#[update]
+fn set_normalization_stats(stats: NormalizationStats) -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    MODEL_STORAGE.with(|storage| {
+        let mut storage_ref = storage.borrow_mut();
+        handle_set_normalization_stats(&mut *storage_ref, stats)
+    })
+}
+
+#[query]
+fn get_normalization_stats() -> NormalizationStats {
+    MODEL_STORAGE.with(|storage| {
+        let storage_ref = storage.borrow();
+        handle_get_normalization_stats(&*storage_ref)
+    })
+}
+
+#[update]
+fn unload_model() -> VeriChainResult<String> {
+    utils::require_controller()?;
+    
+    VERICHAIN_MODEL.with(|model| {
+        let mut model_ref = model.borrow_mut();
+        handle_unload_model(&mut *model_ref)
+    })
+}
+
 // Query functions
+#[query]
+fn validate_upload_plan(metadata: ModelMetadata, chunk_hashes: Vec<String>) -> VeriChainResult<UploadPlanReport> {
+    handle_validate_upload_plan(metadata, chunk_hashes)
+}
+
+#[query]
+fn recommend_chunk_plan(original_size: u64) -> VeriChainResult<UploadPlanReport> {
+    handle_recommend_chunk_plan(original_size)
+}
+
 #[query]
 fn get_upload_status() -> UploadStatus {
     MODEL_STORAGE.with(|storage| {
@@ -160,11 +529,36 @@ fn get_upload_status() -> UploadStatus {
     })
 }
 
+#[query]
+fn check_storage_integrity() -> StorageIntegrityReport {
+    MODEL_STORAGE.with(|storage| {
+        let storage_ref = storage.borrow();
+        handle_check_storage_integrity(&*storage_ref)
+    })
+}
+
 #[query]
 fn get_initialization_status() -> InitializationStatus {
+    MODEL_STORAGE.with(|storage| {
+        CONFIG.with(|config| {
+            let storage_ref = storage.borrow();
+            let config_ref = config.borrow();
+            handle_get_initialization_status(&*storage_ref, &*config_ref)
+        })
+    })
+}
+
+// Controller-only: returns the raw bytes of one uploaded chunk so auditors can
+// rebuild the model off-chain and hash it independently
+#[query]
+fn get_model_chunk_data(chunk_id: u32) -> Option<Vec<u8>> {
+    if let Err(e) = utils::require_controller() {
+        ic_cdk::trap(e);
+    }
+    
     MODEL_STORAGE.with(|storage| {
         let storage_ref = storage.borrow();
-        handle_get_initialization_status(&*storage_ref)
+        handle_get_model_chunk_data(&*storage_ref, chunk_id)
     })
 }
 
@@ -176,17 +570,118 @@ fn get_model_info() -> ModelInfo {
     })
 }
 
+#[query]
+fn get_model_classes() -> Vec<String> {
+    handle_get_model_classes()
+}
+
+#[query]
+fn get_analytics() -> AnalyticsReport {
+    ANALYTICS.with(|analytics| {
+        let analytics_ref = analytics.borrow();
+        handle_get_analytics(&*analytics_ref)
+    })
+}
+
+// Distribution of the most recent verdict confidences; a shift toward the
+// low end suggests the model or the inputs have drifted
+#[query]
+fn get_recent_confidence_histogram(buckets: u32) -> VeriChainResult<Vec<u32>> {
+    CONFIDENCE_HISTORY.with(|history| {
+        let history_ref = history.borrow();
+        handle_get_confidence_histogram(&*history_ref, buckets)
+    })
+}
+
+#[update]
+fn self_test() -> VeriChainResult<SelfTestReport> {
+    utils::require_controller()?;
+    
+    VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                let model_ref = model.borrow();
+                let storage_ref = storage.borrow();
+                let config_ref = config.borrow();
+                Ok(handle_self_test(&*model_ref, &*storage_ref, &*config_ref))
+            })
+        })
+    })
+}
+
+#[query]
+fn get_recent_errors(limit: Option<u32>) -> VeriChainResult<Vec<ErrorEntry>> {
+    utils::require_controller()?;
+    
+    ERROR_LOG.with(|log| {
+        let log_ref = log.borrow();
+        Ok(handle_get_recent_errors(&*log_ref, limit))
+    })
+}
+
 #[query]
 fn health_check() -> SystemHealth {
     VERICHAIN_MODEL.with(|model| {
         START_TIME.with(|start_time| {
-            let model_ref = model.borrow();
-            let start_time_val = *start_time.borrow();
-            handle_health_check(&*model_ref, start_time_val)
+            INSTALL_TIME.with(|install_time| {
+                LAST_UPGRADE_ERROR.with(|last_upgrade_error| {
+                    let model_ref = model.borrow();
+                    let start_time_val = *start_time.borrow();
+                    let install_time_val = *install_time.borrow();
+                    let last_upgrade_error = last_upgrade_error.borrow().clone();
+                    handle_health_check(&*model_ref, install_time_val, start_time_val, last_upgrade_error)
+                })
+            })
+        })
+    })
+}
+
+#[query]
+fn is_ready_for_analysis() -> bool {
+    VERICHAIN_MODEL.with(|model| {
+        MODEL_STORAGE.with(|storage| {
+            CONFIG.with(|config| {
+                let model_ref = model.borrow();
+                let storage_ref = storage.borrow();
+                let config_ref = config.borrow();
+                handle_is_ready_for_analysis(&*model_ref, &*storage_ref, &*config_ref)
+            })
         })
     })
 }
 
+#[query]
+fn extract_media_forensics(image_data: Vec<u8>) -> VeriChainResult<ForensicReport> {
+    handle_extract_media_forensics(image_data)
+}
+
+#[query]
+fn get_preprocessed_tensor(image_data: Vec<u8>) -> VeriChainResult<PreprocessedTensor> {
+    let include_full_tensor = utils::require_controller().is_ok();
+    MODEL_STORAGE.with(|storage| {
+        let storage_ref = storage.borrow();
+        handle_get_preprocessed_tensor(image_data, &*storage_ref, include_full_tensor)
+    })
+}
+
+#[query]
+fn validate_image_detailed(image_data: Vec<u8>) -> ValidationReport {
+    CONFIG.with(|config| {
+        let config_ref = config.borrow();
+        handle_validate_image_detailed(image_data, &*config_ref)
+    })
+}
+
+#[query]
+fn prescreen(image_data: Vec<u8>) -> VeriChainResult<PrescreenResult> {
+    handle_prescreen(image_data)
+}
+
+#[query]
+fn compute_phash(image_data: Vec<u8>) -> VeriChainResult<String> {
+    handle_compute_phash(image_data)
+}
+
 #[query]
 fn validate_image_format(image_data: Vec<u8>) -> bool {
     handle_validate_image_format(image_data)
@@ -197,5 +692,10 @@ fn get_supported_formats() -> Vec<String> {
     handle_get_supported_formats()
 }
 
+#[query]
+fn get_supported_media() -> SupportedMedia {
+    handle_get_supported_media()
+}
+
 // Export the candid interface
 ic_cdk::export_candid!();
@@ -1,3 +1,4 @@
+pub mod onnx;
 pub mod verichain_model;
 
 pub use verichain_model::*;
@@ -0,0 +1,176 @@
+//! Just enough of the protobuf wire format to read tensor shapes out of an
+//! ONNX model, without a protobuf dependency.
+
+/// `ModelProto.graph`
+const MODEL_GRAPH_FIELD: u64 = 7;
+/// `GraphProto.initializer`
+const GRAPH_INITIALIZER_FIELD: u64 = 5;
+/// `TensorProto.dims`
+const TENSOR_DIMS_FIELD: u64 = 1;
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Number of parameters in an ONNX model: the element counts of its graph's
+/// initializers, summed from their declared shapes. None if the bytes are
+/// not a well-formed ModelProto with a graph.
+pub fn count_initializer_parameters(model: &[u8]) -> Option<u64> {
+    let mut graph = None;
+    for_each_field(model, |number, value| {
+        if let (MODEL_GRAPH_FIELD, FieldValue::Bytes(bytes)) = (number, value) {
+            graph = Some(bytes);
+        }
+        Some(())
+    })?;
+    
+    let mut total: u64 = 0;
+    for_each_field(graph?, |number, value| {
+        if let (GRAPH_INITIALIZER_FIELD, FieldValue::Bytes(tensor)) = (number, value) {
+            total = total.checked_add(tensor_element_count(tensor)?)?;
+        }
+        Some(())
+    })?;
+    
+    Some(total)
+}
+
+/// Product of a TensorProto's dims; 1 for a scalar.
+fn tensor_element_count(tensor: &[u8]) -> Option<u64> {
+    let mut count: u64 = 1;
+    for_each_field(tensor, |number, value| {
+        if number != TENSOR_DIMS_FIELD {
+            return Some(());
+        }
+        match value {
+            FieldValue::Varint(dim) => count = count.checked_mul(dim)?,
+            // Packed repeated int64
+            FieldValue::Bytes(packed) => {
+                let mut pos = 0;
+                while pos < packed.len() {
+                    count = count.checked_mul(read_varint(packed, &mut pos)?)?;
+                }
+            }
+            FieldValue::Fixed => return None,
+        }
+        Some(())
+    })?;
+    Some(count)
+}
+
+/// Calls `visit` with each top-level field of a message, stopping with None
+/// on malformed input or when `visit` returns None. Length-delimited values
+/// are borrowed, so skipping large weight blobs copies nothing.
+fn for_each_field<'a>(
+    buf: &'a [u8],
+    mut visit: impl FnMut(u64, FieldValue<'a>) -> Option<()>,
+) -> Option<()> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let value = match key & 7 {
+            0 => FieldValue::Varint(read_varint(buf, &mut pos)?),
+            1 => {
+                pos = pos.checked_add(8).filter(|end| *end <= buf.len())?;
+                FieldValue::Fixed
+            }
+            2 => {
+                let len = usize::try_from(read_varint(buf, &mut pos)?).ok()?;
+                let end = pos.checked_add(len)?;
+                let bytes = buf.get(pos..end)?;
+                pos = end;
+                FieldValue::Bytes(bytes)
+            }
+            5 => {
+                pos = pos.checked_add(4).filter(|end| *end <= buf.len())?;
+                FieldValue::Fixed
+            }
+            // Groups are deprecated and never used by ONNX
+            _ => return None,
+        };
+        visit(key >> 3, value)?;
+    }
+    Some(())
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return out;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn bytes_field(number: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out = varint(number << 3 | 2);
+        out.extend(varint(payload.len() as u64));
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn varint_field(number: u64, value: u64) -> Vec<u8> {
+        let mut out = varint(number << 3);
+        out.extend(varint(value));
+        out
+    }
+
+    fn model_with_graph(graph: &[u8]) -> Vec<u8> {
+        let mut model = varint_field(1, 8); // ir_version
+        model.extend(bytes_field(2, b"pytorch")); // producer_name
+        model.extend(bytes_field(MODEL_GRAPH_FIELD, graph));
+        model
+    }
+
+    #[test]
+    fn sums_packed_and_unpacked_initializer_shapes() {
+        // 768 x 3 x 16 x 16 with packed dims, plus raw data that must be skipped
+        let packed_dims: Vec<u8> = [768, 3, 16, 16].iter().flat_map(|d| varint(*d)).collect();
+        let mut conv = bytes_field(TENSOR_DIMS_FIELD, &packed_dims);
+        conv.extend(varint_field(2, 1)); // data_type = FLOAT
+        conv.extend(bytes_field(9, &[0u8; 64])); // raw_data
+        
+        // 768 with unpacked dims
+        let mut bias = varint_field(TENSOR_DIMS_FIELD, 768);
+        bias.extend(bytes_field(8, b"bias")); // name
+        
+        let mut graph = bytes_field(1, b"node");
+        graph.extend(bytes_field(GRAPH_INITIALIZER_FIELD, &conv));
+        graph.extend(bytes_field(GRAPH_INITIALIZER_FIELD, &bias));
+        
+        let model = model_with_graph(&graph);
+        assert_eq!(count_initializer_parameters(&model), Some(768 * 3 * 16 * 16 + 768));
+    }
+
+    #[test]
+    fn rejects_truncated_or_graphless_input() {
+        let graph = bytes_field(GRAPH_INITIALIZER_FIELD, &varint_field(TENSOR_DIMS_FIELD, 10));
+        let model = model_with_graph(&graph);
+        assert_eq!(count_initializer_parameters(&model), Some(10));
+        assert_eq!(count_initializer_parameters(&model[..model.len() - 1]), None);
+        assert_eq!(count_initializer_parameters(&varint_field(1, 8)), None);
+    }
+}
@@ -1,12 +1,27 @@
-use crate::types::{PredictionResult, RawScores, VeriChainResult};
-use crate::preprocessing::preprocess_image_from_bytes;
+use crate::types::{PredictionResult, RawScores, VeriChainResult, NormalizationStats, NUM_CLASSES};
+use crate::preprocessing::preprocess_image;
+use image::DynamicImage;
+use crate::utils::SUPPORTED_IMAGE_FORMATS;
+use crate::model::onnx::count_initializer_parameters;
 use sha2::{Sha256, Digest};
 
+/// Accepted size range for the reconstructed ONNX model, in bytes.
+pub const MIN_MODEL_SIZE_BYTES: usize = 300_000_000;
+pub const MAX_MODEL_SIZE_BYTES: usize = 400_000_000;
+
+/// Version string reported for the deployed model; see `ModelVersion::parse`.
+pub const MODEL_VERSION: &str = "VeriChain-ViT-v1.0";
+
 pub struct VeriChainModel {
     model_data: Option<Vec<u8>>,
     model_loaded: bool,
     model_hash: Option<String>,
-    total_parameters: u64,
+    /// Hash a controller confirmed via `verify_hash`; the model counts as
+    /// verified only while this matches the loaded model's hash.
+    verified_hash: Option<String>,
+    /// Counted once at load time; walking the model's protobuf is too slow
+    /// to repeat per query.
+    parameter_count: Option<u64>,
 }
 
 impl VeriChainModel {
@@ -15,12 +30,13 @@ impl VeriChainModel {
             model_data: None,
             model_loaded: false,
             model_hash: None,
-            total_parameters: 85_800_000, // 85.8M parameters for ViT model
+            verified_hash: None,
+            parameter_count: None,
         })
     }
 
     pub fn load_from_bytes(&mut self, model_data: &[u8]) -> VeriChainResult<()> {
-        let expected_size_range = (300_000_000, 400_000_000);
+        let expected_size_range = (MIN_MODEL_SIZE_BYTES, MAX_MODEL_SIZE_BYTES);
         let actual_size = model_data.len();
         
         if actual_size < expected_size_range.0 || actual_size > expected_size_range.1 {
@@ -40,11 +56,15 @@ impl VeriChainModel {
         self.model_data = Some(model_data.to_vec());
         self.model_hash = Some(hash);
         self.model_loaded = true;
+        self.parameter_count = count_initializer_parameters(model_data);
         
         ic_cdk::println!("🎉 REAL ONNX model loaded successfully: {:.2}MB", 
                          actual_size as f64 / (1024.0 * 1024.0));
         ic_cdk::println!("📋 Model hash: {}", self.model_hash.as_ref().unwrap());
-        ic_cdk::println!("🧠 VeriChain Vision Transformer with {} parameters ready!", self.total_parameters);
+        match self.parameter_count() {
+            Some(count) => ic_cdk::println!("🧠 VeriChain Vision Transformer with {} parameters ready!", count),
+            None => ic_cdk::println!("⚠️ Could not read parameter count from the model's initializers"),
+        }
         
         // Verify ONNX format
         if model_data.len() > 16 {
@@ -55,7 +75,7 @@ impl VeriChainModel {
         Ok(())
     }
 
-    pub fn predict(&self, image_data: &[u8]) -> VeriChainResult<PredictionResult> {
+    pub fn predict(&self, image: DynamicImage, stats: &NormalizationStats) -> VeriChainResult<PredictionResult> {
         if !self.model_loaded {
             return Err("Model not loaded".to_string());
         }
@@ -66,17 +86,42 @@ impl VeriChainModel {
         ic_cdk::println!("🧠 Running prediction with REAL ONNX model ({:.2}MB)", 
                          model_data.len() as f64 / (1024.0 * 1024.0));
         
-        let processed_image = preprocess_image_from_bytes(image_data)?;
+        let processed_image = preprocess_image(image, stats)?;
         
         // Use the REAL reconstructed ONNX model for inference
         self.run_onnx_inference(model_data, &processed_image)
     }
 
+    /// Runs inference on a tensor that has already been preprocessed to the
+    /// model input shape.
+    pub fn predict_preprocessed(&self, processed_image: &[f32]) -> VeriChainResult<PredictionResult> {
+        if !self.model_loaded {
+            return Err("Model not loaded".to_string());
+        }
+
+        let model_data = self.model_data.as_ref()
+            .ok_or_else(|| "Model data not available after loading".to_string())?;
+        
+        self.run_onnx_inference(model_data, processed_image)
+    }
+
     fn run_onnx_inference(&self, model_data: &[u8], processed_image: &[f32]) -> VeriChainResult<PredictionResult> {
         ic_cdk::println!("🔥 Running REAL ONNX inference with reconstructed model...");
         ic_cdk::println!("📊 Model size: {:.2}MB (REAL 327MB ONNX model)", model_data.len() as f64 / (1024.0 * 1024.0));
         ic_cdk::println!("🎯 Input shape: {:?}", self.get_input_shape());
         
+        let (channels, height, width) = self.get_input_shape();
+        let expected_len = (channels * height * width) as usize;
+        if processed_image.len() != expected_len {
+            return Err(Self::describe_input_size_mismatch(
+                processed_image.len(), channels as usize, height as usize, width as usize,
+            ));
+        }
+        
+        if cfg!(feature = "deterministic-inference") {
+            return Ok(Self::deterministic_prediction(processed_image));
+        }
+        
         // Verify we have the correct reconstructed ONNX model
         if model_data.len() < 300_000_000 {
             return Err(format!("Invalid model size: {:.2}MB. Expected ~327MB ONNX model", 
@@ -98,7 +143,53 @@ impl VeriChainModel {
         Ok(PredictionResult::new(raw_scores))
     }
 
-    fn extract_vit_features_from_onnx(&self, model_data: &[u8], processed_image: &[f32]) -> VeriChainResult<[f32; 3]> {
+    /// Test-build stand-in for inference: class weights come from the first
+    /// three bytes of the tensor's SHA-256, so the same input always yields
+    /// the same verdict regardless of floating-point details in the model.
+    fn deterministic_prediction(processed_image: &[f32]) -> PredictionResult {
+        let mut hasher = Sha256::new();
+        for value in processed_image {
+            hasher.update(value.to_le_bytes());
+        }
+        let digest = hasher.finalize();
+        
+        let weights = [digest[0], digest[1], digest[2]].map(|b| b as f64 + 1.0);
+        let total: f64 = weights.iter().sum();
+        
+        PredictionResult::new(RawScores::from_probabilities(weights.map(|w| w / total)))
+    }
+
+    /// A wrong tensor length means preprocessing is broken, so spell out what
+    /// was expected and the most likely reason rather than a bare mismatch.
+    fn describe_input_size_mismatch(actual: usize, channels: usize, height: usize, width: usize) -> String {
+        let expected = channels * height * width;
+        let plane = height * width;
+        
+        let likely_cause = if actual == 0 {
+            "preprocessing produced an empty tensor".to_string()
+        } else if actual % plane == 0 {
+            format!("wrong channel count: {} channels of {}x{} instead of {}",
+                    actual / plane, width, height, channels)
+        } else if actual % channels == 0 {
+            let pixels = actual / channels;
+            let side = (pixels as f64).sqrt().round() as usize;
+            if side * side == pixels {
+                format!("wrong resolution: {}x{} with {} channels instead of {}x{}",
+                        side, side, channels, width, height)
+            } else {
+                format!("wrong resolution: {} pixels per channel instead of {}", pixels, plane)
+            }
+        } else {
+            "tensor length matches neither the expected channel count nor the expected resolution".to_string()
+        };
+        
+        format!(
+            "Invalid input data size: got {} values, expected {} ({}x{}x{} CHW). Likely cause: {}",
+            actual, expected, channels, height, width, likely_cause
+        )
+    }
+
+    fn extract_vit_features_from_onnx(&self, model_data: &[u8], processed_image: &[f32]) -> VeriChainResult<[f32; NUM_CLASSES]> {
         ic_cdk::println!("🔍 Extracting features from ONNX model ({:.2}MB)", 
                          model_data.len() as f64 / (1024.0 * 1024.0));
         
@@ -122,8 +213,7 @@ impl VeriChainModel {
         let features = self.compute_vit_forward_pass(processed_image, &patch_weights, &attention_weights)?;
         let logits = self.compute_final_classification(&features, &classifier_weights)?;
         
-        ic_cdk::println!("🎯 Final logits from REAL ONNX: [{:.3}, {:.3}, {:.3}]", 
-                         logits[0], logits[1], logits[2]);
+        ic_cdk::println!("🎯 Final logits from REAL ONNX: {:.3?}", logits);
         
         Ok(logits)
     }
@@ -239,98 +329,223 @@ impl VeriChainModel {
         Ok(global_features)
     }
     
-    fn compute_final_classification(&self, features: &[f32], classifier_weights: &[f32]) -> VeriChainResult<[f32; 3]> {
+    fn compute_final_classification(&self, features: &[f32], classifier_weights: &[f32]) -> VeriChainResult<[f32; NUM_CLASSES]> {
         let embed_dim = 768;
-        let num_classes = 3;
         
         if features.len() != embed_dim {
             return Err(format!("Feature dimension mismatch: {} != {}", features.len(), embed_dim));
         }
         
-        let mut logits = [0.0; 3];
-        
-        // Final classification layer: features @ classifier_weights
-        for class_idx in 0..num_classes {
-            let mut score = 0.0;
-            
-            for feat_idx in 0..embed_dim {
-                let weight_idx = (class_idx * embed_dim + feat_idx) % classifier_weights.len();
-                score += features[feat_idx] * classifier_weights[weight_idx];
-            }
-            
-            logits[class_idx] = score;
+        if classifier_weights.len() < embed_dim * NUM_CLASSES {
+            return Err(format!(
+                "Insufficient classifier weights: {} (need {} for [{}, {}])",
+                classifier_weights.len(), embed_dim * NUM_CLASSES, embed_dim, NUM_CLASSES
+            ));
         }
         
-        ic_cdk::println!("🎯 Classification completed with real weights: [{:.3}, {:.3}, {:.3}]", 
-                         logits[0], logits[1], logits[2]);
+        // Final classification layer: [1, 768] x [768, NUM_CLASSES] -> [1, NUM_CLASSES]
+        let output = Self::matrix_multiply(
+            features,
+            &classifier_weights[..embed_dim * NUM_CLASSES],
+            1,
+            embed_dim,
+            NUM_CLASSES,
+        )?;
+        
+        let output_len = output.len();
+        let logits: [f32; NUM_CLASSES] = output.try_into().map_err(|_| {
+            format!("Model output length {} does not match NUM_CLASSES ({})", output_len, NUM_CLASSES)
+        })?;
+        
+        ic_cdk::println!("🎯 Classification completed with real weights: {:.3?}", logits);
         
         Ok(logits)
     }
 
-    fn apply_softmax(&self, logits: [f32; 3]) -> RawScores {
-        ic_cdk::println!("🧮 Applying softmax to REAL logits: [{:.6}, {:.6}, {:.6}]", 
-                         logits[0], logits[1], logits[2]);
-        
-        // Apply proper softmax normalization to real logits
-        let max_logit = logits.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    /// Row-major GEMM: `a` is `[m, k]`, `b` is `[k, n]`, result is `[m, n]`.
+    /// Loops run i-k-j so the inner loop walks both `b` and the output row
+    /// contiguously, which keeps the instruction count down on wasm.
+    fn matrix_multiply(a: &[f32], b: &[f32], m: usize, k: usize, n: usize) -> VeriChainResult<Vec<f32>> {
+        if a.len() != m * k {
+            return Err(format!("MatMul lhs size mismatch: {} != {}x{}", a.len(), m, k));
+        }
+        if b.len() != k * n {
+            return Err(format!("MatMul rhs size mismatch: {} != {}x{}", b.len(), k, n));
+        }
         
-        // Subtract max for numerical stability
-        let exp_logits: Vec<f32> = logits.iter()
-            .map(|&x| (x - max_logit).exp())
-            .collect();
+        let mut output = vec![0.0f32; m * n];
+        for i in 0..m {
+            let out_row = &mut output[i * n..(i + 1) * n];
+            for p in 0..k {
+                let a_ip = a[i * k + p];
+                if a_ip == 0.0 {
+                    continue;
+                }
+                let b_row = &b[p * n..(p + 1) * n];
+                for (out, &b_pj) in out_row.iter_mut().zip(b_row) {
+                    *out += a_ip * b_pj;
+                }
+            }
+        }
         
-        let sum_exp: f32 = exp_logits.iter().sum();
+        Ok(output)
+    }
+
+    fn apply_softmax(&self, logits: [f32; NUM_CLASSES]) -> RawScores {
+        ic_cdk::println!("🧮 Applying softmax to REAL logits: {:.6?}", logits);
         
-        // Convert to probabilities
-        let real_prob = (exp_logits[0] / sum_exp) as f64;
-        let ai_prob = (exp_logits[1] / sum_exp) as f64;
-        let deepfake_prob = (exp_logits[2] / sum_exp) as f64;
+        let probabilities = softmax(logits);
         
-        ic_cdk::println!("🎯 Softmax probabilities: real={:.6}, ai={:.6}, deepfake={:.6}", 
-                         real_prob, ai_prob, deepfake_prob);
+        ic_cdk::println!("🎯 Softmax probabilities (real, ai, deepfake): {:.6?}", probabilities);
         
         // Verify probabilities sum to 1.0
-        let total = real_prob + ai_prob + deepfake_prob;
+        let total: f64 = probabilities.iter().sum();
         ic_cdk::println!("✅ Probability sum verification: {:.6} (should be ~1.0)", total);
         
-        RawScores::new(real_prob, ai_prob, deepfake_prob)
+        RawScores::from_probabilities(probabilities)
     }
 
     pub fn is_loaded(&self) -> bool {
         self.model_loaded
     }
 
+    /// Inference path `predict` would take right now. With a model loaded
+    /// that is "onnx_simplified": `extract_vit_features_from_onnx` samples
+    /// floats from byte ranges of the model file rather than executing its
+    /// graph ("deterministic_test" in builds with the
+    /// `deterministic-inference` feature). "onnx_real" is reserved for a real
+    /// ONNX forward pass, which this build doesn't have. "none" means no
+    /// model is loaded, so analysis is rejected.
+    pub fn inference_mode(&self) -> &'static str {
+        if !(self.model_loaded && self.model_data.is_some()) {
+            "none"
+        } else if cfg!(feature = "deterministic-inference") {
+            "deterministic_test"
+        } else {
+            "onnx_simplified"
+        }
+    }
+
     pub fn get_input_shape(&self) -> (u32, u32, u32) {
         (3, 224, 224)
     }
 
     pub fn get_supported_formats(&self) -> Vec<String> {
-        vec!["PNG".to_string(), "JPEG".to_string(), "JPG".to_string()]
+        SUPPORTED_IMAGE_FORMATS.iter().map(|f| f.to_string()).collect()
+    }
+
+    pub fn get_model_data(&self) -> Option<&Vec<u8>> {
+        self.model_data.as_ref()
+    }
+
+    /// Parameters in the loaded model, summed from its initializer shapes.
+    pub fn parameter_count(&self) -> Option<u64> {
+        self.parameter_count
     }
 
-    pub fn get_model_data(&self) -> Option<Vec<u8>> {
-        self.model_data.clone()
+    pub fn model_size_bytes(&self) -> usize {
+        self.model_data.as_ref().map_or(0, |data| data.len())
     }
 
     pub fn get_model_hash(&self) -> Option<String> {
         self.model_hash.clone()
     }
 
-    #[allow(dead_code)]
+    /// Marks the loaded model as verified if its hash matches `expected_hash`
+    /// (compared case-insensitively, ignoring surrounding whitespace).
+    pub fn verify_hash(&mut self, expected_hash: &str) -> VeriChainResult<()> {
+        let actual = self.model_hash.clone()
+            .filter(|_| self.model_loaded)
+            .ok_or_else(|| "Model not loaded".to_string())?;
+        
+        let expected = expected_hash.trim().to_lowercase();
+        if actual != expected {
+            self.verified_hash = None;
+            return Err(format!(
+                "Model hash mismatch: loaded model is {}, expected {}",
+                actual, expected
+            ));
+        }
+        
+        self.verified_hash = Some(expected);
+        Ok(())
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.model_loaded && self.verified_hash.is_some() && self.verified_hash == self.model_hash
+    }
+
+    pub fn get_verified_hash(&self) -> Option<String> {
+        self.verified_hash.clone()
+    }
+
+    /// A model that reports itself loaded without going through
+    /// `load_from_bytes`, which insists on a full-size ONNX file.
+    #[cfg(test)]
+    pub(crate) fn loaded_for_test(model_data: Vec<u8>) -> Self {
+        Self {
+            model_data: Some(model_data),
+            model_loaded: true,
+            model_hash: None,
+            verified_hash: None,
+            parameter_count: None,
+        }
+    }
+
     pub fn unload(&mut self) {
         self.model_data = None;
         self.model_loaded = false;
         self.model_hash = None;
+        self.verified_hash = None;
+        self.parameter_count = None;
     }
 }
 
+/// Softmax over the model's logits, in logit order.
+fn softmax(logits: [f32; NUM_CLASSES]) -> [f64; NUM_CLASSES] {
+    let max_logit = logits.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    
+    // Subtract max for numerical stability
+    let exp_logits = logits.map(|x| (x - max_logit).exp());
+    
+    let sum_exp: f32 = exp_logits.iter().sum();
+    
+    exp_logits.map(|x| (x / sum_exp) as f64)
+}
+
 impl Default for VeriChainModel {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             model_data: None,
             model_loaded: false,
             model_hash: None,
-            total_parameters: 85_800_000,
+            verified_hash: None,
+            parameter_count: None,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(scores: &RawScores) -> f64 {
+        scores.real + scores.ai_generated + scores.deepfake
+    }
+
+    #[test]
+    fn confident_logits_give_scores_summing_to_one() {
+        let model = VeriChainModel::new().unwrap();
+        let softmaxed = model.apply_softmax([10.0, 0.0, 0.0]);
+        // Rebuild through the constructor so a clamp there would show up
+        let scores = RawScores::new(softmaxed.real, softmaxed.ai_generated, softmaxed.deepfake);
+        assert!((sum(&scores) - 1.0).abs() < 1e-5, "sum was {}", sum(&scores));
+        assert!(scores.real > 0.99);
+
+        let display = scores.display_scores();
+        assert!((sum(&display) - 1.0).abs() < 1e-5, "display sum was {}", sum(&display));
+        for score in [display.real, display.ai_generated, display.deepfake] {
+            assert!((0.01..=0.98).contains(&score), "display score {} out of range", score);
+        }
+    }
+}
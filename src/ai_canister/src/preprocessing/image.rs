@@ -1,4 +1,6 @@
-use image::DynamicImage;
+use crate::types::NormalizationStats;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use image::imageops::FilterType;
 
 const MODEL_INPUT_WIDTH: u32 = 224;
 const MODEL_INPUT_HEIGHT: u32 = 224;
@@ -8,24 +10,31 @@ pub fn decode_image(data: &[u8]) -> Result<DynamicImage, String> {
         .map_err(|e| format!("Failed to decode image: {}", e))
 }
 
-pub fn preprocess_image(image: DynamicImage) -> Result<Vec<f32>, String> {
+pub fn preprocess_image(image: DynamicImage, stats: &NormalizationStats) -> Result<Vec<f32>, String> {
+    preprocess_image_with_filter(image, FilterType::Lanczos3, stats)
+}
+
+pub fn preprocess_image_with_filter(
+    image: DynamicImage,
+    filter: FilterType,
+    stats: &NormalizationStats,
+) -> Result<Vec<f32>, String> {
+    stats.validate()?;
+    
     // Resize to model input size (224x224)
     let resized = image.resize_exact(
         MODEL_INPUT_WIDTH, 
         MODEL_INPUT_HEIGHT, 
-        image::imageops::FilterType::Lanczos3
+        filter
     );
     
     // Convert to RGB if needed
     let rgb_image = resized.to_rgb8();
     
     // Convert to tensor format (CHW - Channels, Height, Width)
-    // Normalize pixel values to [0.0, 1.0] and apply ImageNet normalization
+    // Normalize pixel values to [0.0, 1.0] and apply the model's channel statistics
     let mut tensor_data = Vec::with_capacity(3 * MODEL_INPUT_HEIGHT as usize * MODEL_INPUT_WIDTH as usize);
-    
-    // ImageNet mean and std for normalization
-    let mean = [0.485, 0.456, 0.406];
-    let std = [0.229, 0.224, 0.225];
+    let (mean, std) = (&stats.mean, &stats.std);
     
     // Process each channel separately (R, G, B)
     for channel in 0..3 {
@@ -41,12 +50,90 @@ pub fn preprocess_image(image: DynamicImage) -> Result<Vec<f32>, String> {
     Ok(tensor_data)
 }
 
-pub fn preprocess_image_from_bytes(image_data: &[u8]) -> Result<Vec<f32>, String> {
+/// Fits the image inside the model input size without distortion and pads
+/// the remainder with black, so extreme aspect ratios keep their content
+/// recognisable.
+pub fn letterbox(image: &DynamicImage) -> DynamicImage {
+    let fitted = image.resize(MODEL_INPUT_WIDTH, MODEL_INPUT_HEIGHT, FilterType::Lanczos3).to_rgb8();
+    let (width, height) = fitted.dimensions();
+    
+    let mut canvas = RgbImage::new(MODEL_INPUT_WIDTH, MODEL_INPUT_HEIGHT);
+    image::imageops::overlay(
+        &mut canvas,
+        &fitted,
+        ((MODEL_INPUT_WIDTH - width) / 2) as i64,
+        ((MODEL_INPUT_HEIGHT - height) / 2) as i64,
+    );
+    DynamicImage::ImageRgb8(canvas)
+}
+
+/// Ratio of the longer side to the shorter one.
+pub fn aspect_ratio(image: &DynamicImage) -> f64 {
+    let (width, height) = image.dimensions();
+    width.max(height) as f64 / width.min(height).max(1) as f64
+}
+
+/// 64-bit difference hash: each bit records whether a pixel of a 9x8
+/// grayscale thumbnail is brighter than its right neighbour. Recompression
+/// and resizing flip few bits, so Hamming distance approximates visual
+/// similarity.
+pub fn difference_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = image.grayscale().resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = thumbnail.get_pixel(x, y)[0];
+            let right = thumbnail.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+/// Shannon entropy, in bits (0 to 8), of the luminance histogram at model
+/// input resolution. Solid and near-uniform images score close to 0.
+pub fn luminance_entropy(image: &DynamicImage) -> f64 {
+    let luma = image.resize_exact(MODEL_INPUT_WIDTH, MODEL_INPUT_HEIGHT, FilterType::Triangle).to_luma8();
+    
+    let mut histogram = [0u32; 256];
+    for pixel in luma.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    
+    let total = (MODEL_INPUT_WIDTH * MODEL_INPUT_HEIGHT) as f64;
+    histogram.iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+pub fn preprocess_image_from_bytes(image_data: &[u8], stats: &NormalizationStats) -> Result<Vec<f32>, String> {
     let image = decode_image(image_data)?;
-    preprocess_image(image)
+    preprocess_image(image, stats)
 }
 
-#[allow(dead_code)]
 pub fn get_model_input_shape() -> (u32, u32, u32) {
     (3, MODEL_INPUT_HEIGHT, MODEL_INPUT_WIDTH) // Channels, Height, Width
 }
+
+/// Keeps every `stride`-th value along both spatial axes of a CHW tensor,
+/// preserving channel order.
+pub fn downsample_tensor(tensor: &[f32], stride: u32) -> Vec<f32> {
+    let (channels, height, width) = get_model_input_shape();
+    let stride = stride.max(1);
+    
+    let mut sampled = Vec::new();
+    for channel in 0..channels {
+        for y in (0..height).step_by(stride as usize) {
+            for x in (0..width).step_by(stride as usize) {
+                let index = ((channel * height + y) * width + x) as usize;
+                sampled.push(tensor[index]);
+            }
+        }
+    }
+    sampled
+}
@@ -0,0 +1,62 @@
+use crate::types::prediction::{PredictionLabel, PredictionResult};
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use candid::CandidType;
+
+/// Usage counters kept across upgrades.
+#[derive(Default, Clone, Serialize, Deserialize, CandidType)]
+pub struct AnalyticsState {
+    format_counts: BTreeMap<String, u64>,
+    authentic_detected: u64,
+    deepfake_detected: u64,
+    uncertain: u64,
+    total_instructions_consumed: u128,
+}
+
+impl AnalyticsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_format(&mut self, format: &str) {
+        *self.format_counts.entry(format.to_string()).or_insert(0) += 1;
+    }
+
+    /// Buckets a verdict: AI-generated and deepfake both count as
+    /// manipulated, and anything not confident enough counts as uncertain.
+    pub fn record_verdict(&mut self, prediction: &PredictionResult, min_confidence: f64) {
+        if prediction.uncertain || prediction.raw_confidence < min_confidence {
+            self.uncertain += 1;
+            return;
+        }
+        
+        match prediction.label {
+            PredictionLabel::Real => self.authentic_detected += 1,
+            PredictionLabel::AIGenerated | PredictionLabel::Deepfake => self.deepfake_detected += 1,
+            PredictionLabel::Quarantined => {}
+        }
+    }
+
+    pub fn verdict_counts(&self) -> (u64, u64, u64) {
+        (self.authentic_detected, self.deepfake_detected, self.uncertain)
+    }
+
+    pub fn add_instructions(&mut self, instructions: u64) {
+        self.total_instructions_consumed += instructions as u128;
+    }
+
+    pub fn total_instructions(&self) -> u128 {
+        self.total_instructions_consumed
+    }
+
+    pub fn total_analyses(&self) -> u64 {
+        self.format_counts.values().sum()
+    }
+
+    pub fn format_breakdown(&self) -> Vec<(String, u64)> {
+        self.format_counts
+            .iter()
+            .map(|(format, count)| (format.clone(), *count))
+            .collect()
+    }
+}
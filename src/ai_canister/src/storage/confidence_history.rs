@@ -0,0 +1,34 @@
+use std::collections::VecDeque;
+
+/// Most recent verdict confidences kept for drift monitoring.
+const CONFIDENCE_HISTORY_CAPACITY: usize = 1000;
+
+/// Bounded in-memory record of recent verdict confidences. Not persisted,
+/// like `ErrorLog`: a new code version starts a new baseline.
+#[derive(Default)]
+pub struct ConfidenceHistory {
+    confidences: VecDeque<f64>,
+}
+
+impl ConfidenceHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, confidence: f64) {
+        if self.confidences.len() == CONFIDENCE_HISTORY_CAPACITY {
+            self.confidences.pop_front();
+        }
+        self.confidences.push_back(confidence);
+    }
+
+    /// Counts per equal-width bin over [0, 1]; 1.0 falls in the last bin.
+    pub fn histogram(&self, buckets: usize) -> Vec<u32> {
+        let mut counts = vec![0u32; buckets];
+        for confidence in &self.confidences {
+            let bin = (confidence.clamp(0.0, 1.0) * buckets as f64) as usize;
+            counts[bin.min(buckets - 1)] += 1;
+        }
+        counts
+    }
+}
@@ -0,0 +1,30 @@
+use crate::types::ErrorEntry;
+use std::collections::VecDeque;
+
+/// Most recent failures kept for operator diagnostics.
+const ERROR_LOG_CAPACITY: usize = 100;
+
+/// Bounded in-memory log of recent failures. Not persisted: it is a
+/// debugging aid, and an upgrade is a natural point to start fresh.
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+}
+
+impl ErrorLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: ErrorEntry) {
+        if self.entries.len() == ERROR_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Newest first.
+    pub fn recent(&self, limit: usize) -> Vec<ErrorEntry> {
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+}
@@ -1,3 +1,13 @@
+pub mod analytics;
+pub mod confidence_history;
+pub mod error_log;
 pub mod model_storage;
+pub mod quarantine;
+pub mod upgrade_state;
 
+pub use analytics::*;
+pub use confidence_history::*;
+pub use error_log::*;
 pub use model_storage::*;
+pub use quarantine::*;
+pub use upgrade_state::*;
@@ -1,8 +1,13 @@
-use crate::types::{ModelChunk, ModelMetadata};
+use crate::types::{ChunkErrorKind, ModelChunk, ModelMetadata, NormalizationStats};
+use crate::model::MAX_MODEL_SIZE_BYTES;
+use crate::utils::validate_chunk_id;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use candid::CandidType;
 
+/// Slack over the declared `original_size` before uploads count as oversized.
+const UPLOAD_SIZE_TOLERANCE_BYTES: u64 = 1024 * 1024;
+
 #[derive(Default, Clone, Serialize, Deserialize, CandidType)]
 pub struct ModelStorage {
     chunks: HashMap<u32, ModelChunk>,
@@ -14,6 +19,8 @@ pub struct ModelStorage {
     total_chunks: u32,
     uploaded_chunks: u32,
     processed_chunks: u32,
+    /// Measured assembly cost of the most recent batch, used to size the next
+    instructions_per_chunk: Option<u64>,
 }
 
 impl ModelStorage {
@@ -21,9 +28,45 @@ impl ModelStorage {
         Self::default()
     }
 
-    pub fn store_chunk(&mut self, chunk: ModelChunk) -> Result<(), String> {
-        if self.chunks.contains_key(&chunk.id) {
-            return Err(format!("Chunk {} already exists", chunk.id));
+    /// Stores `chunk`, or says why it was refused so callers can report the
+    /// matching `ChunkErrorKind`.
+    pub fn store_chunk(&mut self, chunk: ModelChunk) -> Result<(), (ChunkErrorKind, String)> {
+        // Chunk ids are only meaningful against uploaded metadata
+        let original_size = match &self.metadata {
+            Some(metadata) => metadata.original_size,
+            None => {
+                return Err((
+                    ChunkErrorKind::MetadataMissing,
+                    "Model metadata must be uploaded before chunks".to_string(),
+                ));
+            }
+        };
+        
+        validate_chunk_id(chunk.id, self.total_chunks).map_err(|e| (ChunkErrorKind::InvalidId, e))?;
+        
+        // Re-uploading a verified chunk replaces the stored copy, which lets a
+        // client repair a single bad chunk. Once assembly has started the
+        // chunks are being consumed, so they can no longer change.
+        if self.initialization_started {
+            return Err((
+                ChunkErrorKind::UploadLocked,
+                format!("Chunk {} rejected: model initialization has already started", chunk.id),
+            ));
+        }
+        
+        // Bound cumulative storage by the declared model size so oversized
+        // uploads can't exhaust canister memory
+        let replaced_bytes = self.chunks.get(&chunk.id).map_or(0, |c| c.data.len() as u64);
+        let stored_after = self.stored_bytes() - replaced_bytes + chunk.data.len() as u64;
+        let byte_limit = original_size.saturating_add(UPLOAD_SIZE_TOLERANCE_BYTES).min(MAX_MODEL_SIZE_BYTES as u64);
+        if stored_after > byte_limit {
+            return Err((
+                ChunkErrorKind::StorageFull,
+                format!(
+                    "Chunk {} rejected: {} bytes would be stored, limit is {} bytes",
+                    chunk.id, stored_after, byte_limit
+                ),
+            ));
         }
         
         self.chunks.insert(chunk.id, chunk);
@@ -47,10 +90,34 @@ impl ModelStorage {
         Ok(())
     }
 
+    /// Total bytes held in uploaded chunks.
+    pub fn stored_bytes(&self) -> u64 {
+        self.chunks.values().map(|c| c.data.len() as u64).sum()
+    }
+
+    pub fn get_chunk(&self, chunk_id: u32) -> Option<&ModelChunk> {
+        self.chunks.get(&chunk_id)
+    }
+
     pub fn get_metadata(&self) -> Option<&ModelMetadata> {
         self.metadata.as_ref()
     }
 
+    /// Statistics from the uploaded metadata, or ImageNet's if none were set.
+    pub fn get_normalization_stats(&self) -> NormalizationStats {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.normalization.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_normalization_stats(&mut self, stats: NormalizationStats) -> Result<(), String> {
+        let metadata = self.metadata.as_mut()
+            .ok_or("Model metadata must be uploaded before normalization statistics")?;
+        metadata.normalization = Some(stats);
+        Ok(())
+    }
+
     pub fn is_upload_complete(&self) -> bool {
         self.upload_complete && self.uploaded_chunks == self.total_chunks
     }
@@ -65,6 +132,21 @@ impl ModelStorage {
         missing
     }
 
+    /// Stored chunk ids at or beyond `total_chunks`, left behind when
+    /// metadata was re-uploaded with fewer chunks.
+    pub fn get_unexpected_chunks(&self) -> Vec<u32> {
+        let mut unexpected: Vec<u32> = self.chunks.keys()
+            .copied()
+            .filter(|id| *id >= self.total_chunks)
+            .collect();
+        unexpected.sort_unstable();
+        unexpected
+    }
+
+    pub fn chunk_count(&self) -> u32 {
+        self.chunks.len() as u32
+    }
+
     pub fn start_initialization(&mut self) -> Result<(), String> {
         if !self.is_upload_complete() {
             return Err("Cannot start initialization: upload not complete".to_string());
@@ -113,6 +195,16 @@ impl ModelStorage {
         Ok(end_chunk - start_chunk)
     }
 
+    pub fn record_batch_cost(&mut self, chunks: u32, instructions: u64) {
+        if chunks > 0 {
+            self.instructions_per_chunk = Some(instructions / chunks as u64);
+        }
+    }
+
+    pub fn get_instructions_per_chunk(&self) -> Option<u64> {
+        self.instructions_per_chunk
+    }
+
     pub fn get_model_data(&self) -> Option<&Vec<u8>> {
         self.model_data.as_ref()
     }
@@ -121,6 +213,11 @@ impl ModelStorage {
         self.is_initialized
     }
 
+    /// True while chunks are being assembled but the model is not complete yet.
+    pub fn is_initialization_in_progress(&self) -> bool {
+        self.initialization_started && !self.is_initialized
+    }
+
     pub fn get_initialization_progress(&self) -> (u32, u32) {
         (self.processed_chunks, self.total_chunks)
     }
@@ -151,3 +248,52 @@ impl ModelStorage {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage_expecting(total_chunks: u32) -> ModelStorage {
+        let mut storage = ModelStorage::new();
+        storage.store_metadata(ModelMetadata {
+            original_file: "model.onnx".to_string(),
+            original_size: 1024,
+            total_chunks,
+            chunk_size_mb: 1.0,
+            version: "VeriChain-ViT-v1.0".to_string(),
+            normalization: None,
+        }).unwrap();
+        storage
+    }
+
+    fn chunk(id: u32, data: Vec<u8>) -> ModelChunk {
+        ModelChunk { id, data, hash: String::new() }
+    }
+
+    #[test]
+    fn reupload_replaces_chunk_without_double_counting() {
+        let mut storage = storage_expecting(2);
+        storage.store_chunk(chunk(0, vec![1; 10])).unwrap();
+        storage.store_chunk(chunk(0, vec![2; 8])).unwrap();
+
+        assert_eq!(storage.chunk_count(), 1);
+        assert_eq!(storage.stored_bytes(), 8);
+        assert_eq!(storage.get_chunk(0).unwrap().data, vec![2; 8]);
+        assert!(!storage.is_upload_complete());
+
+        storage.store_chunk(chunk(1, vec![3; 4])).unwrap();
+        assert_eq!(storage.stored_bytes(), 12);
+        assert!(storage.is_upload_complete());
+    }
+
+    #[test]
+    fn chunks_are_locked_once_initialization_starts() {
+        let mut storage = storage_expecting(1);
+        storage.store_chunk(chunk(0, vec![1; 10])).unwrap();
+        storage.start_initialization().unwrap();
+
+        let (kind, _) = storage.store_chunk(chunk(0, vec![2; 10])).unwrap_err();
+        assert_eq!(kind, ChunkErrorKind::UploadLocked);
+        assert_eq!(storage.get_chunk(0).unwrap().data, vec![1; 10]);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::types::QuarantineEntry;
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use candid::CandidType;
+
+/// Known-bad content, keyed by lowercase SHA-256 hex of the raw input.
+#[derive(Default, Clone, Serialize, Deserialize, CandidType)]
+pub struct QuarantineList {
+    entries: BTreeMap<String, QuarantineEntry>,
+}
+
+impl QuarantineList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, entry: QuarantineEntry) {
+        self.entries.insert(entry.hash.clone(), entry);
+    }
+
+    pub fn remove(&mut self, hash: &str) -> Option<QuarantineEntry> {
+        self.entries.remove(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&QuarantineEntry> {
+        self.entries.get(hash)
+    }
+
+    pub fn list(&self) -> Vec<QuarantineEntry> {
+        self.entries.values().cloned().collect()
+    }
+}
@@ -0,0 +1,70 @@
+use crate::storage::{ModelStorage, AnalyticsState, QuarantineList};
+use crate::types::CanisterConfig;
+use candid::{CandidType, Deserialize};
+
+/// Everything carried across an upgrade, saved with a single `stable_save`.
+#[derive(CandidType, Deserialize)]
+pub struct UpgradeState {
+    pub model_storage: ModelStorage,
+    pub model_data: Option<Vec<u8>>,
+    pub model_hash: Option<String>,
+    pub model_loaded: bool,
+    pub verified_model_hash: Option<String>,
+    pub config: Option<CanisterConfig>,
+    pub analytics: Option<AnalyticsState>,
+    pub quarantine: Option<QuarantineList>,
+    pub install_time: Option<u64>,
+}
+
+/// Borrowed counterpart of `UpgradeState` used by `pre_upgrade`. It encodes
+/// to the same Candid record, so the model is serialized straight from the
+/// canister's state instead of being cloned first.
+#[derive(CandidType)]
+pub struct UpgradeStateRef<'a> {
+    pub model_storage: &'a ModelStorage,
+    pub model_data: Option<&'a Vec<u8>>,
+    pub model_hash: Option<String>,
+    pub model_loaded: bool,
+    pub verified_model_hash: Option<String>,
+    pub config: Option<&'a CanisterConfig>,
+    pub analytics: Option<&'a AnalyticsState>,
+    pub quarantine: Option<&'a QuarantineList>,
+    pub install_time: Option<u64>,
+}
+
+impl UpgradeState {
+    /// Rebuilds state saved by releases that predate `UpgradeState`. Those
+    /// wrote `(ModelStorage,)` and, when a model was loaded, overwrote it with
+    /// `(model_data, model_hash, model_loaded)`, so only one of the two is
+    /// ever present.
+    pub fn restore_legacy() -> Option<Self> {
+        if let Ok((model_storage,)) = ic_cdk::storage::stable_restore::<(ModelStorage,)>() {
+            return Some(Self::legacy(model_storage, None, None, false));
+        }
+        
+        ic_cdk::storage::stable_restore::<(Option<Vec<u8>>, Option<String>, bool)>()
+            .ok()
+            .map(|(model_data, model_hash, model_loaded)| {
+                Self::legacy(ModelStorage::new(), model_data, model_hash, model_loaded)
+            })
+    }
+
+    fn legacy(
+        model_storage: ModelStorage,
+        model_data: Option<Vec<u8>>,
+        model_hash: Option<String>,
+        model_loaded: bool,
+    ) -> Self {
+        Self {
+            model_storage,
+            model_data,
+            model_hash,
+            model_loaded,
+            verified_model_hash: None,
+            config: None,
+            analytics: None,
+            quarantine: None,
+            install_time: None,
+        }
+    }
+}
@@ -4,13 +4,102 @@ use crate::types::prediction::PredictionResult;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct MediaAnalysisResult {
+    pub analysis_id: String,
     pub prediction: PredictionResult,
+    /// `prediction` as a sentence for display
+    pub summary: String,
     pub processing_time_ms: u64,
     pub input_size: u32,
     pub model_version: String,
     pub processed_at: u64,
+    pub quarantine_reason: Option<String>,
+    /// Non-fatal issues that may affect the verdict's reliability
+    pub warnings: Vec<String>,
+    /// Source was smaller than `min_native_resolution`, so the model mostly
+    /// saw interpolated detail
+    pub upscaled: bool,
+    /// Decoded color type before conversion to RGB; None when the image
+    /// wasn't decoded (quarantine hits)
+    pub source_color_type: Option<String>,
+    /// Luminance entropy in bits (0 to 8) of the image the model saw
+    pub entropy: Option<f64>,
+    /// Config bias in effect when `prediction.is_manipulated` was decided
+    pub verdict_bias: f64,
 }
 
 impl MediaAnalysisResult {
     // Remove unused constructor
 }
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct FilterPrediction {
+    pub filter: String,
+    pub prediction: PredictionResult,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RobustResult {
+    pub analysis_id: String,
+    pub predictions: Vec<FilterPrediction>,
+    pub label_agreement: bool,
+    pub mean_confidence: f64,
+    pub confidence_variance: f64,
+    pub processed_at: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ThresholdVerdict {
+    pub threshold: f64,
+    pub is_manipulated: bool,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SweepResult {
+    pub analysis_id: String,
+    /// Verdict under the canister's configured policy
+    pub prediction: PredictionResult,
+    /// `is_manipulated` at each requested threshold, in request order
+    pub verdicts: Vec<ThresholdVerdict>,
+    pub processed_at: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ReferenceMatchResult {
+    pub hash_distance: u32,
+    pub perceptually_similar: bool,
+    pub candidate_prediction: PredictionResult,
+    pub reference_prediction: PredictionResult,
+    pub likely_manipulated_copy: bool,
+    pub processed_at: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PreprocessedTensor {
+    /// Channels, height, width of the full tensor
+    pub shape: Vec<u32>,
+    /// SHA-256 over the tensor's little-endian f32 bytes
+    pub tensor_sha256: String,
+    pub downsample_stride: u32,
+    pub downsampled: Vec<f32>,
+    /// Full tensor, returned to controllers only
+    pub tensor: Option<Vec<f32>>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SupportedMedia {
+    pub image_formats: Vec<String>,
+    pub video_formats: Vec<String>,
+    pub social_platforms: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ValidationReport {
+    pub format_ok: bool,
+    pub size_ok: bool,
+    pub dimensions_ok: bool,
+    pub decodable: bool,
+    pub detected_format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub issues: Vec<String>,
+}
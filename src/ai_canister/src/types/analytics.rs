@@ -0,0 +1,13 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AnalyticsReport {
+    pub total_analyses: u64,
+    pub format_breakdown: Vec<(String, u64)>,
+    pub authentic_detected: u64,
+    pub deepfake_detected: u64,
+    pub uncertain: u64,
+    /// Instructions spent on analysis calls over the canister's lifetime
+    pub total_instructions_consumed: u128,
+}
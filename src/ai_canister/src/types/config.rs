@@ -0,0 +1,256 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use crate::types::{ClassDisplayNames, DEFAULT_MANIPULATION_THRESHOLD};
+use crate::utils::normalize_sha256_hex;
+
+/// Largest shift `verdict_bias` may apply to the manipulation threshold.
+pub const MAX_VERDICT_BIAS: f64 = 0.25;
+
+/// Range the manipulation threshold is held to, with or without bias.
+pub const MIN_MANIPULATION_THRESHOLD: f64 = 0.05;
+pub const MAX_MANIPULATION_THRESHOLD: f64 = 0.95;
+
+/// Fields added after the config was first persisted are optional, so state
+/// saved by an older release still decodes; None means the default, read
+/// through the accessor of the same name.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CanisterConfig {
+    /// Predictions whose top two class scores differ by less than this are
+    /// flagged as uncertain.
+    pub low_confidence_margin: f64,
+    /// Refuse to analyze until a controller has verified the loaded model's
+    /// hash with `verify_loaded_model`.
+    pub require_verified_model: Option<bool>,
+    /// Analyses that use more instructions than this are logged; 0 disables
+    /// the log line.
+    pub slow_request_instruction_threshold: Option<u64>,
+    /// Target instructions per initialization message. Batch sizes are
+    /// derived from the measured per-chunk cost; 0 falls back to the static
+    /// sizes.
+    pub init_batch_instruction_budget: Option<u64>,
+    /// Verdicts below this confidence are counted as uncertain in analytics
+    /// instead of authentic or deepfake.
+    pub analytics_min_confidence: Option<f64>,
+    /// Longest-to-shortest side ratio accepted before `resize_exact` would
+    /// distort the image beyond recognition; 0 disables the check.
+    pub max_aspect_ratio: Option<f64>,
+    /// Pad extreme images to a square (with a warning) instead of rejecting
+    /// them.
+    pub letterbox_extreme_aspect_ratio: Option<bool>,
+    /// Lowers (positive) or raises (negative) the manipulation threshold, so
+    /// safety-critical deployments can err toward flagging.
+    pub verdict_bias: Option<f64>,
+    /// Images whose shorter side is below this many pixels are flagged as
+    /// upscaled; 0 disables the check.
+    pub min_native_resolution: Option<u32>,
+    /// Refuse to analyze unless verdicts come from a real ONNX forward pass,
+    /// so neither the simplified weight-sampling path nor a
+    /// `deterministic-inference` test build can serve them as model output.
+    pub require_real_model: Option<bool>,
+    /// Terms for each class in result summaries.
+    pub class_display_names: Option<ClassDisplayNames>,
+    /// Images whose luminance entropy (in bits, 0 to 8) is below this are
+    /// rejected as too uniform to judge; 0 disables the check.
+    pub min_image_entropy: Option<f64>,
+    /// SHA-256 hashes of the models initialization may load; empty allows
+    /// any model.
+    pub approved_model_hashes: Option<Vec<String>>,
+    /// Manipulated-score threshold before `verdict_bias`, within
+    /// [`MIN_MANIPULATION_THRESHOLD`, `MAX_MANIPULATION_THRESHOLD`]; None uses
+    /// `DEFAULT_MANIPULATION_THRESHOLD`.
+    pub confidence_threshold: Option<f64>,
+}
+
+const DEFAULT_SLOW_REQUEST_INSTRUCTION_THRESHOLD: u64 = 10_000_000_000;
+// Half the 40B per-message limit leaves room for model loading
+const DEFAULT_INIT_BATCH_INSTRUCTION_BUDGET: u64 = 20_000_000_000;
+const DEFAULT_MAX_ASPECT_RATIO: f64 = 4.0;
+// The model input side; anything smaller gets interpolated up
+const DEFAULT_MIN_NATIVE_RESOLUTION: u32 = 224;
+
+impl Default for CanisterConfig {
+    fn default() -> Self {
+        Self {
+            low_confidence_margin: 0.05,
+            require_verified_model: None,
+            slow_request_instruction_threshold: None,
+            init_batch_instruction_budget: None,
+            analytics_min_confidence: None,
+            max_aspect_ratio: None,
+            letterbox_extreme_aspect_ratio: None,
+            verdict_bias: None,
+            min_native_resolution: None,
+            require_real_model: None,
+            class_display_names: None,
+            min_image_entropy: None,
+            approved_model_hashes: None,
+            confidence_threshold: None,
+        }
+    }
+}
+
+/// One tunable as reported by `get_effective_config`.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ConfigEntry {
+    pub name: String,
+    pub value: String,
+    pub default_value: String,
+    /// `update_config` replaces the whole config, so "overridden" means the
+    /// value differs from the default rather than that it was ever sent.
+    pub overridden: bool,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct EffectiveConfig {
+    pub entries: Vec<ConfigEntry>,
+    /// Threshold after `verdict_bias` is applied
+    pub manipulation_threshold: f64,
+}
+
+impl CanisterConfig {
+    /// Every tunable as a (name, value) pair, in declaration order.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("low_confidence_margin", self.low_confidence_margin.to_string()),
+            ("require_verified_model", self.require_verified_model().to_string()),
+            ("slow_request_instruction_threshold", self.slow_request_instruction_threshold().to_string()),
+            ("init_batch_instruction_budget", self.init_batch_instruction_budget().to_string()),
+            ("analytics_min_confidence", self.analytics_min_confidence().to_string()),
+            ("max_aspect_ratio", self.max_aspect_ratio().to_string()),
+            ("letterbox_extreme_aspect_ratio", self.letterbox_extreme_aspect_ratio().to_string()),
+            ("verdict_bias", self.verdict_bias().to_string()),
+            ("min_native_resolution", self.min_native_resolution().to_string()),
+            ("require_real_model", self.require_real_model().to_string()),
+            ("class_display_names.real", self.class_display_names().real),
+            ("class_display_names.ai_generated", self.class_display_names().ai_generated),
+            ("class_display_names.deepfake", self.class_display_names().deepfake),
+            ("min_image_entropy", self.min_image_entropy().to_string()),
+            ("approved_model_hashes", self.approved_model_hashes().join(",")),
+            ("confidence_threshold", self.base_threshold().to_string()),
+        ]
+    }
+
+    /// Threshold `is_manipulated` is decided against after applying the bias.
+    pub fn manipulation_threshold(&self) -> f64 {
+        (self.base_threshold() - self.verdict_bias()).clamp(MIN_MANIPULATION_THRESHOLD, MAX_MANIPULATION_THRESHOLD)
+    }
+
+    /// Configured threshold, or the default if none was set.
+    pub fn base_threshold(&self) -> f64 {
+        self.confidence_threshold.unwrap_or(DEFAULT_MANIPULATION_THRESHOLD)
+    }
+
+    pub fn require_verified_model(&self) -> bool {
+        self.require_verified_model.unwrap_or(false)
+    }
+
+    pub fn slow_request_instruction_threshold(&self) -> u64 {
+        self.slow_request_instruction_threshold.unwrap_or(DEFAULT_SLOW_REQUEST_INSTRUCTION_THRESHOLD)
+    }
+
+    pub fn init_batch_instruction_budget(&self) -> u64 {
+        self.init_batch_instruction_budget.unwrap_or(DEFAULT_INIT_BATCH_INSTRUCTION_BUDGET)
+    }
+
+    pub fn analytics_min_confidence(&self) -> f64 {
+        self.analytics_min_confidence.unwrap_or(0.0)
+    }
+
+    pub fn max_aspect_ratio(&self) -> f64 {
+        self.max_aspect_ratio.unwrap_or(DEFAULT_MAX_ASPECT_RATIO)
+    }
+
+    pub fn letterbox_extreme_aspect_ratio(&self) -> bool {
+        self.letterbox_extreme_aspect_ratio.unwrap_or(false)
+    }
+
+    pub fn verdict_bias(&self) -> f64 {
+        self.verdict_bias.unwrap_or(0.0)
+    }
+
+    pub fn min_native_resolution(&self) -> u32 {
+        self.min_native_resolution.unwrap_or(DEFAULT_MIN_NATIVE_RESOLUTION)
+    }
+
+    pub fn require_real_model(&self) -> bool {
+        self.require_real_model.unwrap_or(false)
+    }
+
+    pub fn class_display_names(&self) -> ClassDisplayNames {
+        self.class_display_names.clone().unwrap_or_default()
+    }
+
+    pub fn min_image_entropy(&self) -> f64 {
+        self.min_image_entropy.unwrap_or(0.0)
+    }
+
+    pub fn approved_model_hashes(&self) -> &[String] {
+        self.approved_model_hashes.as_deref().unwrap_or_default()
+    }
+
+    /// Whether a model with this hash may be loaded.
+    pub fn is_model_approved(&self, model_hash: &str) -> bool {
+        let approved = self.approved_model_hashes();
+        approved.is_empty()
+            || approved.iter().any(|h| h.trim().eq_ignore_ascii_case(model_hash))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.low_confidence_margin) {
+            return Err(format!(
+                "low_confidence_margin must be within [0.0, 1.0], got {}",
+                self.low_confidence_margin
+            ));
+        }
+        
+        let analytics_min_confidence = self.analytics_min_confidence();
+        if !(0.0..=1.0).contains(&analytics_min_confidence) {
+            return Err(format!(
+                "analytics_min_confidence must be within [0.0, 1.0], got {}",
+                analytics_min_confidence
+            ));
+        }
+        
+        let max_aspect_ratio = self.max_aspect_ratio();
+        if max_aspect_ratio != 0.0 && !(max_aspect_ratio >= 1.0 && max_aspect_ratio.is_finite()) {
+            return Err(format!(
+                "max_aspect_ratio must be 0 (disabled) or a finite value >= 1.0, got {}",
+                max_aspect_ratio
+            ));
+        }
+        
+        let verdict_bias = self.verdict_bias();
+        if !(-MAX_VERDICT_BIAS..=MAX_VERDICT_BIAS).contains(&verdict_bias) {
+            return Err(format!(
+                "verdict_bias must be within [-{}, {}], got {}",
+                MAX_VERDICT_BIAS, MAX_VERDICT_BIAS, verdict_bias
+            ));
+        }
+        
+        let min_image_entropy = self.min_image_entropy();
+        if !(0.0..=8.0).contains(&min_image_entropy) {
+            return Err(format!(
+                "min_image_entropy must be within [0.0, 8.0] bits, got {}",
+                min_image_entropy
+            ));
+        }
+        
+        if let Some(threshold) = self.confidence_threshold {
+            // Anything outside this range would be clamped without notice
+            if !(MIN_MANIPULATION_THRESHOLD..=MAX_MANIPULATION_THRESHOLD).contains(&threshold) {
+                return Err(format!(
+                    "confidence_threshold must be within [{}, {}], got {}",
+                    MIN_MANIPULATION_THRESHOLD, MAX_MANIPULATION_THRESHOLD, threshold
+                ));
+            }
+        }
+        
+        for hash in self.approved_model_hashes() {
+            normalize_sha256_hex(hash).map_err(|e| format!("approved_model_hashes: {}", e))?;
+        }
+        
+        self.class_display_names().validate()?;
+        
+        Ok(())
+    }
+}
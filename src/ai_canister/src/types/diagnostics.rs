@@ -0,0 +1,30 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ErrorEntry {
+    pub timestamp: u64,
+    pub caller: String,
+    pub endpoint: String,
+    /// "invalid_input" when the request failed validation, otherwise
+    /// "analysis_failed"
+    pub error_kind: String,
+    pub message: String,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SelfTestStage {
+    /// "decode", "preprocess", "inference" or "decision"
+    pub stage: String,
+    pub passed: bool,
+    /// Instructions the stage consumed
+    pub instructions: u64,
+    pub detail: String,
+}
+
+/// Stages run in pipeline order and stop at the first failure.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub stages: Vec<SelfTestStage>,
+}
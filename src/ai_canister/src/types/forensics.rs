@@ -0,0 +1,31 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct ForensicReport {
+    pub format: String,
+    pub has_exif: bool,
+    pub has_xmp: bool,
+    /// No EXIF, XMP, comment or text metadata of any kind was found.
+    pub metadata_stripped: bool,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub software: Option<String>,
+    pub creator_tool: Option<String>,
+    pub capture_time: Option<String>,
+    pub present_fields: Vec<String>,
+    pub editing_software_hints: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum RiskLevel {
+    Low,
+    Elevated,
+    High,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PrescreenResult {
+    pub risk_level: RiskLevel,
+    pub reasons: Vec<String>,
+}
@@ -4,10 +4,20 @@ pub mod analysis;
 pub mod model;
 pub mod status;
 pub mod result;
+pub mod config;
+pub mod forensics;
+pub mod analytics;
+pub mod moderation;
+pub mod diagnostics;
 
 // Re-export main types for easy access
-pub use prediction::{RawScores, PredictionResult};
-pub use analysis::MediaAnalysisResult;
-pub use model::{ModelInfo, ModelChunk, ModelMetadata};
-pub use status::{SystemHealth, UploadStatus, InitializationStatus};
+pub use prediction::{ClassDisplayNames, RawScores, PredictionResult, NUM_CLASSES, CLASS_LABELS, DEFAULT_MANIPULATION_THRESHOLD};
+pub use analysis::{MediaAnalysisResult, FilterPrediction, RobustResult, ThresholdVerdict, SweepResult, ReferenceMatchResult, PreprocessedTensor, SupportedMedia, ValidationReport};
+pub use model::{ModelInfo, ModelVersion, ModelChunk, ModelMetadata, UploadPlanReport, ChunkErrorKind, ChunkUploadResult, NormalizationStats};
+pub use status::{SystemHealth, UploadStatus, StorageIntegrityReport, InitializationStatus};
 pub use result::VeriChainResult;
+pub use config::{CanisterConfig, ConfigEntry, EffectiveConfig};
+pub use forensics::{ForensicReport, PrescreenResult, RiskLevel};
+pub use analytics::AnalyticsReport;
+pub use moderation::QuarantineEntry;
+pub use diagnostics::{ErrorEntry, SelfTestStage, SelfTestReport};
@@ -7,13 +7,58 @@ pub struct ModelInfo {
     pub input_size: (u32, u32),
     pub supported_formats: Vec<String>,
     pub model_loaded: bool,
+    /// Summed from the loaded model's initializer shapes; None if no model
+    /// is loaded or its graph can't be parsed
     pub total_parameters: Option<u64>,
+    /// `version` split into its parts; None if it doesn't follow the
+    /// `<family>-<variant>-v<major>.<minor>[.<patch>]` convention
+    pub parsed_version: Option<ModelVersion>,
 }
 
 impl ModelInfo {
     // Remove unused constructors
 }
 
+/// Structured form of a model version string such as `VeriChain-ViT-v1.0`,
+/// so clients can check compatibility by major version instead of matching
+/// strings.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub variant: String,
+}
+
+impl ModelVersion {
+    /// Parses `<family>-<variant>-v<major>.<minor>[.<patch>]`; a missing
+    /// patch component is 0.
+    pub fn parse(version: &str) -> Result<Self, String> {
+        let invalid = || format!(
+            "Invalid model version '{}': expected <family>-<variant>-v<major>.<minor>[.<patch>]",
+            version
+        );
+        
+        let (name, numbers) = version.rsplit_once("-v").ok_or_else(invalid)?;
+        let (_family, variant) = name.split_once('-').ok_or_else(invalid)?;
+        if variant.is_empty() {
+            return Err(invalid());
+        }
+        
+        let parts = numbers.split('.')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let (major, minor, patch) = match parts.as_slice() {
+            [major, minor] => (*major, *minor, 0),
+            [major, minor, patch] => (*major, *minor, *patch),
+            _ => return Err(invalid()),
+        };
+        
+        Ok(Self { major, minor, patch, variant: variant.to_string() })
+    }
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct ModelChunk {
     pub id: u32,
@@ -25,6 +70,41 @@ impl ModelChunk {
     // Remove unused constructors
 }
 
+/// Why a chunk upload failed, so clients can decide whether retrying helps.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ChunkErrorKind {
+    /// Data doesn't match the supplied hash: re-read the file and retry.
+    HashMismatch,
+    /// Supplied hash isn't a 64-character hex digest: fix the client.
+    InvalidHash,
+    /// No room left for the chunk: retrying won't help.
+    StorageFull,
+    /// Chunk id is outside the declared range.
+    InvalidId,
+    /// Metadata must be uploaded before chunks.
+    MetadataMissing,
+    /// Initialization has started, so chunks can no longer change.
+    UploadLocked,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ChunkUploadResult {
+    pub chunk_id: u32,
+    pub success: bool,
+    pub message: String,
+    pub error_kind: Option<ChunkErrorKind>,
+}
+
+impl ChunkUploadResult {
+    pub fn success(chunk_id: u32, message: String) -> Self {
+        Self { chunk_id, success: true, message, error_kind: None }
+    }
+
+    pub fn failure(chunk_id: u32, error_kind: ChunkErrorKind, message: String) -> Self {
+        Self { chunk_id, success: false, message, error_kind: Some(error_kind) }
+    }
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct ModelMetadata {
     pub original_file: String,
@@ -32,8 +112,57 @@ pub struct ModelMetadata {
     pub total_chunks: u32,
     pub chunk_size_mb: f64,
     pub version: String,
+    /// Per-channel statistics the model was trained with; ImageNet if unset
+    pub normalization: Option<NormalizationStats>,
 }
 
 impl ModelMetadata {
     // Remove unused constructors
 }
+
+/// Per-channel (R, G, B) mean and standard deviation applied to pixel values
+/// in [0, 1] before inference.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct NormalizationStats {
+    pub mean: Vec<f32>,
+    pub std: Vec<f32>,
+}
+
+impl Default for NormalizationStats {
+    /// ImageNet statistics
+    fn default() -> Self {
+        Self {
+            mean: vec![0.485, 0.456, 0.406],
+            std: vec![0.229, 0.224, 0.225],
+        }
+    }
+}
+
+impl NormalizationStats {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mean.len() != 3 || self.std.len() != 3 {
+            return Err(format!(
+                "Expected 3 mean and 3 std values (R, G, B), got {} and {}",
+                self.mean.len(), self.std.len()
+            ));
+        }
+        
+        if self.mean.iter().any(|m| !m.is_finite()) {
+            return Err("Normalization mean values must be finite".to_string());
+        }
+        
+        if self.std.iter().any(|s| !s.is_finite() || *s <= 0.0) {
+            return Err("Normalization std values must be finite and positive".to_string());
+        }
+        
+        Ok(())
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UploadPlanReport {
+    pub expected_chunks: u32,
+    pub chunk_size_bytes: u64,
+    pub last_chunk_size_bytes: u64,
+    pub total_ingress_calls: u32,
+}
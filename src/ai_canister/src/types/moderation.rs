@@ -0,0 +1,9 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct QuarantineEntry {
+    pub hash: String,
+    pub reason: String,
+    pub quarantined_at: u64,
+}
@@ -1,17 +1,73 @@
 use candid::{CandidType, Deserialize};
 use serde::Serialize;
 
+/// Number of classes the model outputs, in logit order: real, AI-generated,
+/// deepfake. Change this (and `RawScores`) to support a different head.
+pub const NUM_CLASSES: usize = 3;
+
+/// Class names in logit order, matching the `RawScores` field names.
+pub const CLASS_LABELS: [&str; NUM_CLASSES] = ["real", "ai_generated", "deepfake"];
+
+/// Combined AI-generated + deepfake probability at or above which content is
+/// flagged as manipulated, before any configured bias.
+pub const DEFAULT_MANIPULATION_THRESHOLD: f64 = 0.5;
+
+// RawScores has one named field per class
+const _: () = assert!(NUM_CLASSES == 3, "RawScores must have one field per model class");
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub enum PredictionLabel {
     Real,
     AIGenerated, 
     Deepfake,
+    /// Input matched the quarantine list; the model was not run.
+    Quarantined,
 }
 
 impl PredictionLabel {
     // Remove unused methods - keep enum clean
 }
 
+/// Terms used for each class in human-readable text, so deployments can
+/// match their audience's vocabulary (e.g. "synthetic", "manipulated").
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ClassDisplayNames {
+    pub real: String,
+    pub ai_generated: String,
+    pub deepfake: String,
+}
+
+impl Default for ClassDisplayNames {
+    fn default() -> Self {
+        Self {
+            real: "authentic".to_string(),
+            ai_generated: "AI-generated".to_string(),
+            deepfake: "a deepfake".to_string(),
+        }
+    }
+}
+
+impl ClassDisplayNames {
+    /// Longest accepted display name, in bytes.
+    const MAX_LEN: usize = 64;
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (class, name) in [("real", &self.real), ("ai_generated", &self.ai_generated), ("deepfake", &self.deepfake)] {
+            if name.trim().is_empty() || name.len() > Self::MAX_LEN || name.chars().any(char::is_control) {
+                return Err(format!(
+                    "Display name for {} must be 1-{} bytes without control characters, got {:?}",
+                    class, Self::MAX_LEN, name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowest score `display_scores` reports for any class; the highest is
+/// `1 - (NUM_CLASSES - 1) * DISPLAY_SCORE_FLOOR`.
+const DISPLAY_SCORE_FLOOR: f64 = 0.01;
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct RawScores {
     pub real: f64,
@@ -21,13 +77,40 @@ pub struct RawScores {
 
 impl RawScores {
     pub fn new(real: f64, ai_generated: f64, deepfake: f64) -> Self {
-        Self {
-            real: real.max(0.01).min(0.98),
-            ai_generated: ai_generated.max(0.01).min(0.98),
-            deepfake: deepfake.max(0.01).min(0.98),
-        }
+        Self { real, ai_generated, deepfake }
+    }
+
+    /// Scores mapped into [0.01, 0.98] so a UI never shows absolute
+    /// certainty. The mapping is linear, so the result still sums to 1 and
+    /// keeps the scores' order. Presentation only: do math on the raw scores.
+    pub fn display_scores(&self) -> Self {
+        let scale = 1.0 - NUM_CLASSES as f64 * DISPLAY_SCORE_FLOOR;
+        let normalized = Self::normalized([self.real, self.ai_generated, self.deepfake]);
+        Self::new(
+            DISPLAY_SCORE_FLOOR + normalized.real * scale,
+            DISPLAY_SCORE_FLOOR + normalized.ai_generated * scale,
+            DISPLAY_SCORE_FLOOR + normalized.deepfake * scale,
+        )
+    }
+
+    /// Builds scores from model probabilities in logit order, rescaled so
+    /// rounding in the f32 softmax can't leave the sum off 1.
+    pub fn from_probabilities(probabilities: [f64; NUM_CLASSES]) -> Self {
+        Self::normalized(probabilities)
     }
 
+    fn normalized(scores: [f64; NUM_CLASSES]) -> Self {
+        let total: f64 = scores.iter().sum();
+        let [real, ai_generated, deepfake] = if total > 0.0 && total.is_finite() {
+            scores.map(|s| s / total)
+        } else {
+            scores
+        };
+        Self::new(real, ai_generated, deepfake)
+    }
+
+    /// Scores are compared with `total_cmp`, so a NaN from a bad forward
+    /// pass yields a NaN confidence instead of trapping the canister.
     pub fn get_max_score_and_label(&self) -> (f64, PredictionLabel) {
         let scores = [
             (self.ai_generated, PredictionLabel::AIGenerated),
@@ -36,26 +119,128 @@ impl RawScores {
         ];
 
         scores.into_iter()
-            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .max_by(|a, b| a.0.total_cmp(&b.0))
             .unwrap()
     }
+
+    /// Probability mass on the manipulated classes.
+    pub fn manipulated_score(&self) -> f64 {
+        self.ai_generated + self.deepfake
+    }
+
+    /// Gap between the highest and second-highest class score.
+    pub fn top_two_margin(&self) -> f64 {
+        let mut scores = [self.real, self.ai_generated, self.deepfake];
+        scores.sort_by(|a, b| b.total_cmp(a));
+        scores[0] - scores[1]
+    }
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct PredictionResult {
     pub label: PredictionLabel,
+    /// Same value as `raw_confidence`, kept for clients that predate the
+    /// raw/display split
     pub confidence: f64,
+    /// Model probability of `label`, unclamped
+    pub raw_confidence: f64,
+    /// `raw_confidence` clamped to [0.1, 0.99] for presentation
+    pub display_confidence: f64,
     pub raw_scores: RawScores,
+    /// `raw_scores` mapped into [0.01, 0.98] for presentation; see `RawScores::display_scores`
+    pub display_scores: RawScores,
+    pub uncertain: bool,
+    /// Binary flag decision, made against the configured threshold rather
+    /// than by argmax
+    pub is_manipulated: bool,
 }
 
 impl PredictionResult {
     pub fn new(raw_scores: RawScores) -> Self {
         let (confidence, label) = raw_scores.get_max_score_and_label();
         
+        let is_manipulated = raw_scores.manipulated_score() >= DEFAULT_MANIPULATION_THRESHOLD;
+        
         Self {
             label,
-            confidence: confidence.max(0.1).min(0.99),
+            confidence,
+            raw_confidence: confidence,
+            display_confidence: confidence.clamp(0.1, 0.99),
+            display_scores: raw_scores.display_scores(),
             raw_scores,
+            uncertain: false,
+            is_manipulated,
+        }
+    }
+
+    /// Verdict for blocklisted content. Scores are zero because no
+    /// inference was performed.
+    pub fn quarantined() -> Self {
+        Self {
+            label: PredictionLabel::Quarantined,
+            confidence: 1.0,
+            raw_confidence: 1.0,
+            display_confidence: 1.0,
+            raw_scores: RawScores { real: 0.0, ai_generated: 0.0, deepfake: 0.0 },
+            display_scores: RawScores { real: 0.0, ai_generated: 0.0, deepfake: 0.0 },
+            uncertain: false,
+            is_manipulated: true,
         }
     }
+
+    /// Flags the prediction as uncertain when the model could not separate
+    /// its top two classes by at least `margin`.
+    pub fn apply_low_confidence_margin(&mut self, margin: f64) {
+        self.uncertain = self.raw_scores.top_two_margin() < margin;
+    }
+
+    /// One plain-English sentence for non-technical users, e.g. "This image
+    /// is likely AI-generated (78% confidence)."
+    pub fn summary(&self, names: &ClassDisplayNames) -> String {
+        let verdict = match self.label {
+            PredictionLabel::Quarantined => {
+                return "This image matches quarantined content and was not analyzed.".to_string();
+            }
+            _ if self.uncertain => {
+                return format!(
+                    "The model could not reach a clear verdict for this image ({:.0}% confidence).",
+                    self.display_confidence * 100.0
+                );
+            }
+            PredictionLabel::Real => &names.real,
+            PredictionLabel::AIGenerated => &names.ai_generated,
+            PredictionLabel::Deepfake => &names.deepfake,
+        };
+        
+        let band = if self.raw_confidence >= 0.85 {
+            "very likely"
+        } else if self.raw_confidence >= 0.6 {
+            "likely"
+        } else {
+            "possibly"
+        };
+        
+        format!(
+            "This image is {} {} ({:.0}% confidence).",
+            band, verdict, self.display_confidence * 100.0
+        )
+    }
+
+    /// Re-decides `is_manipulated` against `threshold`.
+    pub fn apply_manipulation_threshold(&mut self, threshold: f64) {
+        self.is_manipulated = self.raw_scores.manipulated_score() >= threshold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nan_scores_do_not_panic() {
+        let scores = RawScores::new(f64::NAN, 0.2, 0.1);
+        let (confidence, _) = scores.get_max_score_and_label();
+        assert!(confidence.is_nan());
+        assert!(scores.top_two_margin().is_nan());
+    }
 }
@@ -5,9 +5,16 @@ use serde::Serialize;
 pub struct SystemHealth {
     pub status: String,
     pub model_loaded: bool,
+    pub model_verified: bool,
+    pub inference_mode: String,
+    /// Since the original install
     pub uptime_seconds: u64,
+    /// Since the last install or upgrade
+    pub current_version_uptime_seconds: u64,
     pub memory_usage_mb: f64,
     pub cycle_balance: u64,
+    /// Set when the last upgrade couldn't restore state or reload the model
+    pub last_upgrade_error: Option<String>,
 }
 
 impl SystemHealth {
@@ -27,6 +34,22 @@ impl UploadStatus {
     // Remove unused methods
 }
 
+/// Cross-check of stored chunks against the uploaded metadata.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StorageIntegrityReport {
+    pub metadata_present: bool,
+    pub chunks_in_map: u32,
+    pub chunks_declared: u32,
+    pub missing_ids: Vec<u32>,
+    /// Stored ids outside the declared range
+    pub unexpected_ids: Vec<u32>,
+    pub stored_bytes: u64,
+    pub declared_bytes: u64,
+    /// Every declared chunk is present, nothing extra is stored, and the
+    /// stored bytes add up to the declared model size
+    pub consistent: bool,
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct InitializationStatus {
     pub is_initialized: bool,
@@ -36,6 +59,8 @@ pub struct InitializationStatus {
     pub current_size_mb: f64,
     pub estimated_total_size_mb: f64,
     pub error_message: Option<String>,
+    pub next_batch_size: u32,
+    pub instructions_per_chunk: Option<u64>,
 }
 
 impl InitializationStatus {
@@ -0,0 +1,10 @@
+use crate::types::VeriChainResult;
+use ic_cdk::api::{is_controller, msg_caller};
+
+pub fn require_controller() -> VeriChainResult<()> {
+    let caller = msg_caller();
+    if !is_controller(&caller) {
+        return Err(format!("Caller {} is not a controller of this canister", caller));
+    }
+    Ok(())
+}
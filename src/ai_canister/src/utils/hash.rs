@@ -6,6 +6,13 @@ pub fn calculate_hash(data: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Correlation id for one analysis request: a prefix of the input hash plus
+/// the request timestamp, so repeated submissions still get distinct ids.
+pub fn generate_analysis_id(input: &[u8], timestamp_ns: u64) -> String {
+    let input_hash = calculate_hash(input);
+    format!("{}-{:x}", &input_hash[..16], timestamp_ns)
+}
+
 pub fn verify_chunk_integrity(chunk_data: &[u8], expected_hash: &str) -> bool {
     let actual_hash = calculate_hash(chunk_data);
     actual_hash == expected_hash
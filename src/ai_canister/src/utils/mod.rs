@@ -1,5 +1,8 @@
+pub mod auth;
 pub mod hash;
+pub mod random;
 pub mod validation;
 
+pub use auth::*;
 pub use hash::*;
 pub use validation::*;
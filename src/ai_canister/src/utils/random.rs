@@ -0,0 +1,28 @@
+//! Randomness for the AI canister.
+//!
+//! Every replica has to compute the same result, so nothing in this crate may
+//! draw on local entropy (`thread_rng`, `getrandom`, seeding from `time()`).
+//! Random bytes come only from the management canister's `raw_rand`, which is
+//! an inter-canister call and therefore only usable from update methods.
+//! Query methods must stay fully deterministic.
+
+use crate::types::VeriChainResult;
+use ic_cdk::management_canister::raw_rand;
+
+/// Fetches 32 bytes of consensus randomness from `raw_rand`.
+#[allow(dead_code)]
+pub async fn get_randomness() -> VeriChainResult<Vec<u8>> {
+    raw_rand()
+        .await
+        .map_err(|e| format!("Failed to obtain randomness from raw_rand: {}", e))
+}
+
+// Any dependency that reaches for OS entropy would make replicas diverge, so
+// fail loudly instead of silently returning replica-local bytes.
+#[cfg(target_arch = "wasm32")]
+fn deny_local_entropy(_buf: &mut [u8]) -> Result<(), getrandom::Error> {
+    ic_cdk::trap("Local entropy is not available on the IC; use utils::random::get_randomness()")
+}
+
+#[cfg(target_arch = "wasm32")]
+getrandom::register_custom_getrandom!(deny_local_entropy);
@@ -1,7 +1,23 @@
 const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024; // 10MB
 const MIN_IMAGE_SIZE: usize = 1024; // 1KB
+pub const MAX_CHUNK_SIZE_BYTES: u64 = 2_000_000; // Stay under the 2MiB ingress limit
+/// Every image format `validate_image_data` accepts; the single source for
+/// capability listings.
+pub const SUPPORTED_IMAGE_FORMATS: [&str; 3] = ["PNG", "JPEG", "JPG"];
+pub const MAX_METADATA_TEXT_BYTES: usize = 4 * 1024; // Client strings are persisted across upgrades
 
 pub fn validate_image_data(data: &[u8]) -> Result<(), String> {
+    validate_image_size(data)?;
+    
+    // Check for common image format headers
+    if !is_supported_format(data) {
+        return Err("Unsupported image format. Only PNG, JPEG, and JPG are supported".to_string());
+    }
+    
+    Ok(())
+}
+
+pub fn validate_image_size(data: &[u8]) -> Result<(), String> {
     if data.is_empty() {
         return Err("Image data is empty".to_string());
     }
@@ -16,30 +32,30 @@ pub fn validate_image_data(data: &[u8]) -> Result<(), String> {
                           data.len(), MAX_IMAGE_SIZE));
     }
     
-    // Check for common image format headers
-    if !is_supported_format(data) {
-        return Err("Unsupported image format. Only PNG, JPEG, and JPG are supported".to_string());
-    }
-    
     Ok(())
 }
 
 pub fn is_supported_format(data: &[u8]) -> bool {
+    detect_image_format(data).is_some()
+}
+
+/// Identifies the image format from its magic bytes.
+pub fn detect_image_format(data: &[u8]) -> Option<&'static str> {
     if data.len() < 8 {
-        return false;
+        return None;
     }
     
     // PNG magic bytes
     if data.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
-        return true;
+        return Some("PNG");
     }
     
     // JPEG magic bytes
     if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
-        return true;
+        return Some("JPEG");
     }
     
-    false
+    None
 }
 
 pub fn validate_chunk_id(chunk_id: u32, total_chunks: u32) -> Result<(), String> {
@@ -50,6 +66,21 @@ pub fn validate_chunk_id(chunk_id: u32, total_chunks: u32) -> Result<(), String>
     Ok(())
 }
 
+/// Rejects oversized or control-character-laden client strings before they
+/// are stored and echoed back in status responses.
+pub fn validate_metadata_text(field: &str, value: &str) -> Result<(), String> {
+    if value.len() > MAX_METADATA_TEXT_BYTES {
+        return Err(format!("{} too long: {} bytes (maximum {} bytes)", 
+                          field, value.len(), MAX_METADATA_TEXT_BYTES));
+    }
+    
+    if value.chars().any(char::is_control) {
+        return Err(format!("{} contains control characters", field));
+    }
+    
+    Ok(())
+}
+
 pub fn validate_batch_size(batch_size: u32) -> Result<(), String> {
     const MIN_BATCH_SIZE: u32 = 1;
     const MAX_BATCH_SIZE: u32 = 200;
@@ -66,3 +97,17 @@ pub fn validate_batch_size(batch_size: u32) -> Result<(), String> {
     
     Ok(())
 }
+
+/// Trims and lowercases a client-supplied SHA-256 digest so it compares
+/// equal to `calculate_hash` output.
+pub fn normalize_sha256_hex(hash: &str) -> Result<String, String> {
+    let normalized = hash.trim().to_lowercase();
+    if !is_valid_sha256_hex(&normalized) {
+        return Err(format!("'{}' is not a 64-character SHA-256 hex digest", hash));
+    }
+    Ok(normalized)
+}
+
+pub fn is_valid_sha256_hex(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
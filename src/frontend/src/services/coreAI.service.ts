@@ -128,7 +128,7 @@ export class CoreAIService {
       
       return {
         is_deepfake: isDeepfake,
-        confidence: analysisResult.prediction.confidence,
+        confidence: analysisResult.prediction.display_confidence,
         media_type: 'image',
         processing_time_ms: processingTime,
         metadata: JSON.stringify({
@@ -201,7 +201,7 @@ export class CoreAIService {
       
       return {
         is_deepfake: isDeepfake,
-        confidence: analysisResult.prediction.confidence,
+        confidence: analysisResult.prediction.display_confidence,
         media_type: 'video',
         processing_time_ms: processingTime,
         metadata: JSON.stringify({
@@ -393,7 +393,7 @@ export class CoreAIService {
       
       return {
         is_deepfake: isDeepfake,
-        confidence: analysisResult.prediction.confidence,
+        confidence: analysisResult.prediction.display_confidence,
         media_type: 'social_media',
         processing_time_ms: Number(analysisResult.processing_time_ms),
         metadata: JSON.stringify({
@@ -414,11 +414,11 @@ export class CoreAIService {
         },
         analysis_details: {
           classification: isDeepfake ? 'deepfake' : 'authentic',
-          class_confidence: analysisResult.prediction.confidence,
+          class_confidence: analysisResult.prediction.display_confidence,
           classes: {
-            real_probability: analysisResult.prediction.raw_scores.real,
-            ai_generated_probability: analysisResult.prediction.raw_scores.ai_generated,
-            deepfake_probability: analysisResult.prediction.raw_scores.deepfake
+            real_probability: analysisResult.prediction.display_scores.real,
+            ai_generated_probability: analysisResult.prediction.display_scores.ai_generated,
+            deepfake_probability: analysisResult.prediction.display_scores.deepfake
           }
         }
       };
@@ -506,7 +506,8 @@ export class CoreAIService {
 
   /**
    * Perform health check on AI canister
-   * @returns Promise<boolean> - True if canister is healthy
+   * @returns Promise<boolean> - True if canister is healthy, or has a model
+   * loaded that a controller hasn't verified yet
    */
   async healthCheck(): Promise<boolean> {
     try {
@@ -514,7 +515,8 @@ export class CoreAIService {
       
       // Get health status from canister
       const result = await this.actor.health_check();
-      return result.status === 'healthy' && result.model_loaded;
+      const ready = result.status === 'healthy' || result.status === 'loaded_unverified';
+      return ready && result.model_loaded;
     } catch (error) {
       console.error('Health check failed:', error);
       return false;